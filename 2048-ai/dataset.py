@@ -0,0 +1,30 @@
+import glob
+import os
+
+import numpy as np
+
+# action indices used by the Rust engine's `Move::ALL`
+ACTIONS = ["U", "D", "L", "R"]
+
+
+def load_dataset(dir: str) -> dict[str, np.ndarray]:
+    """Loads the shards written by `engine2048 export` into flat arrays.
+
+    Returns a dict with:
+      obs:    (N, 16) float32, log2 of each tile (0 = empty)
+      action: (N,) int64, index into ACTIONS
+      legal:  (N, 4) bool, which actions change the board
+      ret:    (N,) float32, score gained from this move to the end of the game
+    """
+    paths = sorted(glob.glob(os.path.join(dir, "shard-*.npy")))
+    if len(paths) == 0:
+        raise FileNotFoundError(f"no shards found in {dir}")
+
+    records = np.concatenate([np.load(path) for path in paths])
+
+    return {
+        "obs": records["board"].astype(np.float32),
+        "action": records["action"].astype(np.int64),
+        "legal": (records["legal"][:, None] >> np.arange(4)) & 1 == 1,
+        "ret": records["return"].astype(np.float32),
+    }
@@ -0,0 +1,61 @@
+import json
+import os
+import unittest
+from unittest import mock
+
+from engine import Board
+
+CASES_PATH = os.path.join(
+    os.path.dirname(__file__), "..", "2048-engine", "conformance", "cases.jsonl"
+)
+
+
+class TestConformance(unittest.TestCase):
+    """Checks the rules against the corpus generated by the Rust engine
+    (`cargo run -- conformance conformance/cases.jsonl` in 2048-engine)."""
+
+    def load_cases(self):
+        with open(CASES_PATH) as f:
+            return [json.loads(line) for line in f if line.strip()]
+
+    def test_cases(self):
+        b = Board()
+        for i, case in enumerate(self.load_cases()):
+            if "spawn" in case:
+                continue
+
+            board = list(case["board"])
+            changed, score = b._make_move(board, case["move"])
+
+            with self.subTest(line=i + 1):
+                self.assertEqual(board, case["after"])
+                self.assertEqual(score, case["score"])
+                self.assertEqual(changed, case["changed"])
+
+    def test_spawn_cases(self):
+        """Whole moves, with the spawned tile's cell and value roll taken from
+        the case instead of `random`."""
+        for i, case in enumerate(self.load_cases()):
+            if "spawn" not in case:
+                continue
+
+            b = Board()
+            b.board = list(case["board"])
+            b.score = 0
+            b.game_over = False
+
+            spawn = case["spawn"]
+            with mock.patch("random.choice", lambda seq: seq[spawn["nth_empty"]]), mock.patch(
+                "random.random", return_value=spawn["roll"]
+            ):
+                changed, game_over = b.make_move(case["move"])
+
+            with self.subTest(line=i + 1):
+                self.assertEqual(b.board, case["after"])
+                self.assertEqual(b.score, case["score"])
+                self.assertEqual(changed, case["changed"])
+                self.assertEqual(game_over, case["game_over"])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,231 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use engine2048::engine::{Board, Move};
+
+const MOVES_PER_ITER: u64 = 10_000;
+
+// the move path as it was before the allocations were removed, kept here so
+// every run measures the old and new engines side by side
+mod allocating {
+    use engine2048::engine::Move;
+    use rand::{random, seq::SliceRandom};
+
+    #[derive(Clone, Copy)]
+    pub struct Board {
+        board: [u32; 16],
+        score: u32,
+        game_over: bool,
+    }
+
+    impl Board {
+        pub fn new() -> Self {
+            let mut board = [0; 16];
+            let mut numbers: Vec<usize> = (0..16).collect();
+            numbers.shuffle(&mut rand::thread_rng());
+            for &i in &numbers[..2] {
+                board[i] = if random::<f64>() > 0.8 { 4 } else { 2 };
+            }
+
+            Self::from_cells(board)
+        }
+
+        pub fn from_cells(board: [u32; 16]) -> Self {
+            Self {
+                board,
+                score: 0,
+                game_over: false,
+            }
+        }
+
+        // returns (board_changed, game_over)
+        pub fn make_move(&mut self, dir: Move) -> (bool, bool) {
+            let (board_changed, score_increase) = Self::slide(&mut self.board, dir);
+            self.score += score_increase;
+
+            let zero_pos: Vec<usize> = (0..16_usize).filter(|i| self.board[*i] == 0).collect();
+            if !zero_pos.is_empty() && board_changed {
+                let random_zero = zero_pos.choose(&mut rand::thread_rng()).unwrap();
+                self.board[*random_zero] = if random::<f64>() > 0.8 { 4 } else { 2 };
+            }
+            if zero_pos.len() == 1 && board_changed {
+                self.game_over = self.is_game_over();
+            }
+
+            (board_changed, self.game_over)
+        }
+
+        pub fn peek_move(&self, dir: Move) -> Option<Board> {
+            let mut next = *self;
+            let (board_changed, score_increase) = Self::slide(&mut next.board, dir);
+            next.score += score_increase;
+            board_changed.then_some(next)
+        }
+
+        fn slide(board: &mut [u32; 16], dir: Move) -> (bool, u32) {
+            let results: Vec<(bool, u32)> = (0..4)
+                .map(|i| Self::handle_row(board, Self::make_idx_func(dir, i)))
+                .collect();
+
+            (
+                results.iter().any(|e| e.0),
+                results.iter().map(|e| e.1).sum(),
+            )
+        }
+
+        fn make_idx_func(dir: Move, i: usize) -> impl Fn(usize) -> usize {
+            move |j: usize| -> usize {
+                match dir {
+                    Move::Up => 4 * (3 - j) + i,
+                    Move::Down => 4 * j + i,
+                    Move::Left => 4 * i + (3 - j),
+                    Move::Right => 4 * i + j,
+                }
+            }
+        }
+
+        fn is_game_over(&self) -> bool {
+            let zero_pos: Vec<usize> = (0..16_usize).filter(|e| self.board[*e] == 0).collect();
+            if !zero_pos.is_empty() {
+                return false;
+            }
+
+            let mut board_copy = [0; 16];
+            Move::ALL.into_iter().all(|dir| {
+                board_copy.copy_from_slice(&self.board);
+                !Self::slide(&mut board_copy, dir).0
+            })
+        }
+
+        fn handle_row(board: &mut [u32; 16], idx_func: impl Fn(usize) -> usize) -> (bool, u32) {
+            let idx: Vec<usize> = (0..4).map(idx_func).collect();
+
+            let row_sum: u32 = idx.iter().map(|e| board[*e]).sum();
+            if row_sum == 0 {
+                return (false, 0);
+            }
+            let mut board_changed = false;
+
+            let mut shift_right = 0;
+            for i in (0..4).rev() {
+                if board[idx[i]] == 0 {
+                    shift_right += 1;
+                } else if shift_right != 0 {
+                    board_changed = true;
+                    board[idx[i + shift_right]] = board[idx[i]];
+                    board[idx[i]] = 0;
+                }
+            }
+
+            let mut score_increase = 0;
+            for i in (1..4).rev() {
+                if board[idx[i]] == 0 {
+                    break;
+                } else if board[idx[i]] == board[idx[i - 1]] {
+                    board[idx[i]] *= 2;
+                    score_increase += board[idx[i]];
+                    board[idx[i - 1]] = 0;
+                    for j in (0..(i - 1)).rev() {
+                        if board[idx[j]] != 0 {
+                            board[idx[j + 1]] = board[idx[j]];
+                            board[idx[j]] = 0;
+                        }
+                    }
+                }
+            }
+
+            (board_changed || score_increase != 0, score_increase)
+        }
+    }
+}
+
+// cycles through the four moves, starting a new game whenever one ends
+fn make_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_move");
+    group.throughput(Throughput::Elements(MOVES_PER_ITER));
+
+    group.bench_function("cycle", |bench| {
+        let mut b = Board::new();
+        bench.iter(|| {
+            for i in 0..MOVES_PER_ITER as usize {
+                let game_over = b.make_move(black_box(Move::ALL[i % 4])).game_over;
+                if game_over {
+                    b = Board::new();
+                }
+            }
+        })
+    });
+
+    group.bench_function("cycle_allocating", |bench| {
+        let mut b = allocating::Board::new();
+        bench.iter(|| {
+            for i in 0..MOVES_PER_ITER as usize {
+                let (_, game_over) = b.make_move(black_box(Move::ALL[i % 4]));
+                if game_over {
+                    b = allocating::Board::new();
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+// slides without spawning, which isolates the cost of `handle_row`
+fn peek_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("peek_move");
+    group.throughput(Throughput::Elements(4));
+
+    let mut b = Board::new();
+    for i in 0..40 {
+        b.make_move(Move::ALL[i % 4]);
+    }
+
+    group.bench_function("all_dirs", |bench| {
+        bench.iter(|| {
+            for dir in Move::ALL {
+                black_box(black_box(&b).peek_move(dir));
+            }
+        })
+    });
+
+    let old = allocating::Board::from_cells(*b.get_board());
+    group.bench_function("all_dirs_allocating", |bench| {
+        bench.iter(|| {
+            for dir in Move::ALL {
+                black_box(black_box(&old).peek_move(dir).is_some());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn full_games(c: &mut Criterion) {
+    c.bench_function("full_game", |bench| {
+        bench.iter(|| {
+            let mut b = Board::new();
+            let mut i = 0;
+            loop {
+                let game_over = b.make_move(Move::ALL[i % 4]).game_over;
+                if game_over {
+                    break;
+                }
+                i += 1;
+            }
+            black_box(b.num_moves())
+        })
+    });
+
+    c.bench_function("full_game_allocating", |bench| {
+        bench.iter(|| {
+            let mut b = allocating::Board::new();
+            let mut i = 0;
+            while !b.make_move(Move::ALL[i % 4]).1 {
+                i += 1;
+            }
+            black_box(i)
+        })
+    });
+}
+
+criterion_group!(benches, make_moves, peek_moves, full_games);
+criterion_main!(benches);
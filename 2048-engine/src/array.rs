@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 
 type TensorSize = Vec<usize>;
 
@@ -8,7 +8,7 @@ pub struct Tensor {
 
 impl Tensor {
     pub fn new(v: Vec<u32>) -> Self {
-        return Self { _vec: v };
+        Self { _vec: v }
     }
 
     pub fn shape(&self) -> TensorSize {
@@ -27,11 +27,7 @@ impl Tensor {
         let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
 
         for i in 0..self._vec.len() {
-            if !counts.contains_key(&self._vec[i]) {
-                counts.insert(self._vec[i], 0);
-            }
-
-            *counts.get_mut(&self._vec[i]).unwrap() += 1;
+            *counts.entry(self._vec[i]).or_insert(0) += 1;
         }
 
         let mut keys = Vec::new();
@@ -0,0 +1,231 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    engine::{Board, Move, Rules, Spawn, SpawnDraw},
+    heuristics::Evaluator,
+    policy,
+};
+
+// One (board, move) -> (board, score, changed) case for checking other
+// implementations of the rules against this engine. Most cases only slide;
+// those with `spawn` play the whole move, spawning the tile its draw picks,
+// so every case is deterministic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    pub board: [u32; 16],
+    pub dir: Move,
+    pub after: [u32; 16],
+    pub score: u32,
+    pub changed: bool,
+    pub spawn: Option<SpawnCase>,
+}
+
+// the draw given to a whole move and what the move reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnCase {
+    pub draw: SpawnDraw,
+    pub spawned: Option<Spawn>,
+    pub game_over: bool,
+}
+
+pub fn move_name(dir: Move) -> &'static str {
+    match dir {
+        Move::Up => "U",
+        Move::Down => "D",
+        Move::Left => "L",
+        Move::Right => "R",
+    }
+}
+
+pub fn move_from_name(name: &str) -> Option<Move> {
+    Move::ALL.into_iter().find(|dir| move_name(*dir) == name)
+}
+
+impl Case {
+    // the expected result of sliding `board` in `dir` according to this engine
+    pub fn new(board: [u32; 16], dir: Move) -> Self {
+        let start = Board::from_board(board);
+        let (after, score, changed) = match start.peek_move(dir) {
+            Some(next) => (*next.get_board(), next.get_score(), true),
+            None => (board, 0, false),
+        };
+
+        Self {
+            board,
+            dir,
+            after,
+            score,
+            changed,
+            spawn: None,
+        }
+    }
+
+    // the expected result of playing `dir` on `board`, spawning the tile
+    // picked by `draw` if one spawns
+    pub fn with_spawn(board: [u32; 16], dir: Move, draw: SpawnDraw) -> Self {
+        let mut b = Board::from_board(board);
+        let outcome = b.make_move_with_draw(dir, |_| draw);
+
+        Self {
+            board,
+            dir,
+            after: *b.get_board(),
+            score: outcome.score_gained,
+            changed: outcome.changed,
+            spawn: Some(SpawnCase {
+                draw,
+                spawned: outcome.spawned,
+                game_over: outcome.game_over,
+            }),
+        }
+    }
+
+    // a single line of JSON, e.g.
+    // {"board":[...16 cells...],"move":"L","after":[...],"score":4,"changed":true}
+    // with cases that spawn adding
+    // "spawn":{"nth_empty":3,"roll":0.25},"spawned":{"cell":7,"value":2},"game_over":false
+    pub fn to_json(&self) -> String {
+        let cells = |board: &[u32; 16]| {
+            board
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        let spawn = match &self.spawn {
+            None => String::new(),
+            Some(spawn) => format!(
+                ",\"spawn\":{{\"nth_empty\":{},\"roll\":{}}},\"spawned\":{},\"game_over\":{}",
+                spawn.draw.nth_empty,
+                spawn.draw.roll,
+                match spawn.spawned {
+                    Some(s) => format!("{{\"cell\":{},\"value\":{}}}", s.cell, s.value),
+                    None => "null".to_string(),
+                },
+                spawn.game_over
+            ),
+        };
+
+        format!(
+            "{{\"board\":[{}],\"move\":\"{}\",\"after\":[{}],\"score\":{},\"changed\":{}{}}}",
+            cells(&self.board),
+            move_name(self.dir),
+            cells(&self.after),
+            self.score,
+            self.changed,
+            spawn
+        )
+    }
+}
+
+// rows that exercise the merge rules directly, each placed in the top row
+const EDGE_ROWS: [[u32; 4]; 12] = [
+    [0, 0, 0, 0],
+    [2, 0, 0, 0],
+    [2, 2, 0, 0],
+    [2, 0, 0, 2],
+    [2, 2, 2, 0],
+    [2, 2, 2, 2],
+    [4, 4, 8, 8],
+    [2, 2, 4, 8],
+    [8, 4, 2, 2],
+    [4, 2, 2, 4],
+    [2, 4, 8, 16],
+    [1024, 1024, 2048, 0],
+];
+
+// a full board with no legal moves
+const STUCK_BOARD: [u32; 16] = [2, 4, 2, 16, 16, 2, 8, 32, 4, 64, 32, 16, 2, 8, 2, 4];
+
+// one open cell left after sliding left; whether the game ends depends on
+// whether a 2 spawns next to the 2
+const NEARLY_STUCK_BOARD: [u32; 16] = [
+    0, 8, 16, 2, 64, 128, 256, 512, 8, 16, 32, 64, 128, 256, 512, 1024,
+];
+
+// whole moves around the spawn rules: which value each roll gives, and when
+// the game is reported over
+fn spawn_edge_cases() -> Vec<Case> {
+    let mut top_row = [0; 16];
+    top_row[0] = 2;
+
+    let draw = |nth_empty, roll| SpawnDraw { nth_empty, roll };
+    vec![
+        Case::with_spawn(top_row, Move::Right, draw(0, 0.0)),
+        Case::with_spawn(top_row, Move::Right, draw(14, 0.8)),
+        Case::with_spawn(top_row, Move::Right, draw(5, 0.8000001)),
+        // nothing moves, so nothing spawns
+        Case::with_spawn(top_row, Move::Left, draw(0, 0.5)),
+        Case::with_spawn(NEARLY_STUCK_BOARD, Move::Left, draw(0, 0.5)),
+        Case::with_spawn(NEARLY_STUCK_BOARD, Move::Left, draw(0, 0.9)),
+    ]
+}
+
+// edge cases plus every `sample_every`-th position from random and greedy
+// games, each slid in all four directions. The move actually played from
+// those positions, and every move that spawns into the last empty cell, are
+// also kept as whole moves with the spawn drawn for them. Games and spawns
+// are drawn from `seed`.
+pub fn generate(games: usize, sample_every: usize, seed: u64) -> Vec<Case> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut boards: Vec<[u32; 16]> = EDGE_ROWS
+        .iter()
+        .map(|row| {
+            let mut board = [0; 16];
+            board[..4].copy_from_slice(row);
+            board
+        })
+        .collect();
+    boards.push(STUCK_BOARD);
+    let mut moves = spawn_edge_cases();
+
+    let greedy = Evaluator::default();
+    for i in 0..games {
+        let mut b = Board::with_rng(Rules::default(), &mut rng);
+
+        for seen in 0.. {
+            // alternate policies so the corpus also reaches large tiles
+            let dir = if i % 2 == 0 {
+                let legal: Vec<Move> = policy::legal_moves(&b).collect();
+                legal.choose(&mut rng).copied()
+            } else {
+                greedy.best_move(&b)
+            };
+            let Some(dir) = dir else {
+                break;
+            };
+
+            let before = *b.get_board();
+            let mut spawn = None;
+            let outcome = b.make_move_with_draw(dir, |num_empty| {
+                let draw = SpawnDraw {
+                    nth_empty: rng.gen_range(0..num_empty),
+                    roll: rng.gen(),
+                };
+                spawn = Some((draw, num_empty));
+                draw
+            });
+
+            let sampled = seen % sample_every == 0;
+            if sampled {
+                boards.push(before);
+            }
+            if let Some((draw, num_empty)) = spawn {
+                if sampled || num_empty == 1 {
+                    moves.push(Case::with_spawn(before, dir, draw));
+                }
+            }
+
+            if outcome.game_over {
+                break;
+            }
+        }
+    }
+
+    boards
+        .into_iter()
+        .flat_map(|board| Move::ALL.map(|dir| Case::new(board, dir)))
+        .chain(moves)
+        .collect()
+}
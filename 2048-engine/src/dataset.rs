@@ -0,0 +1,255 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    engine::{Board, Move},
+    policy::{self, Policy},
+};
+
+// Datasets are directories of `shard-{n}.npy` files, each holding a 1-D
+// structured array that `numpy.load` reads directly:
+//   board:  16 x u8, log2 of each tile (0 = empty), row-major
+//   action: u8, index into `Move::ALL` (up, down, left, right)
+//   legal:  u8, bit i set if `Move::ALL[i]` changes the board
+//   return: u32, score gained from this move until the end of the game
+const DESCR: &str =
+    "[('board', '|u1', (16,)), ('action', '|u1'), ('legal', '|u1'), ('return', '<u4')]";
+const RECORD_SIZE: usize = 16 + 1 + 1 + 4;
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub board: [u8; 16],
+    pub action: u8,
+    pub legal: u8,
+    pub ret: u32,
+}
+
+impl Record {
+    pub fn action(&self) -> Move {
+        Move::ALL[self.action as usize]
+    }
+
+    pub fn is_legal(&self, dir: Move) -> bool {
+        self.legal & (1 << move_index(dir)) != 0
+    }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.board);
+        out.push(self.action);
+        out.push(self.legal);
+        out.extend_from_slice(&self.ret.to_le_bytes());
+    }
+
+    fn read_from(bytes: &[u8]) -> Self {
+        Self {
+            board: bytes[..16].try_into().unwrap(),
+            action: bytes[16],
+            legal: bytes[17],
+            ret: u32::from_le_bytes(bytes[18..22].try_into().unwrap()),
+        }
+    }
+}
+
+fn move_index(dir: Move) -> usize {
+    Move::ALL.iter().position(|m| *m == dir).unwrap()
+}
+
+fn encode_board(board: &Board) -> [u8; 16] {
+    board
+        .get_board()
+        .map(|v| if v == 0 { 0 } else { v.trailing_zeros() as u8 })
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn shard_path(dir: &Path, shard: usize) -> PathBuf {
+    dir.join(format!("shard-{:05}.npy", shard))
+}
+
+// buffers records and writes them out as a new shard every `shard_size` records
+pub struct ShardWriter {
+    dir: PathBuf,
+    shard_size: usize,
+    records: Vec<Record>,
+    shards_written: usize,
+}
+
+impl ShardWriter {
+    pub fn new(dir: impl AsRef<Path>, shard_size: usize) -> io::Result<Self> {
+        if shard_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shard size must be at least 1",
+            ));
+        }
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            shard_size,
+            records: Vec::with_capacity(shard_size),
+            shards_written: 0,
+        })
+    }
+
+    pub fn push(&mut self, record: Record) -> io::Result<()> {
+        self.records.push(record);
+        if self.records.len() >= self.shard_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    // writes any buffered records as a (possibly short) shard
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.records.is_empty() {
+            return Ok(());
+        }
+
+        write_npy(&shard_path(&self.dir, self.shards_written), &self.records)?;
+        self.shards_written += 1;
+        self.records.clear();
+        Ok(())
+    }
+
+    // flushes and returns the number of shards written
+    pub fn finish(mut self) -> io::Result<usize> {
+        self.flush()?;
+        Ok(self.shards_written)
+    }
+}
+
+fn write_npy(path: &Path, records: &[Record]) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': {}, 'fortran_order': False, 'shape': ({},), }}",
+        DESCR,
+        records.len()
+    );
+
+    // magic + version + header length + header must be a multiple of 64,
+    // with the header ending in a newline
+    let unpadded = NPY_MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(10 + header.len() + records.len() * RECORD_SIZE);
+    out.extend_from_slice(NPY_MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for record in records {
+        record.write_to(&mut out);
+    }
+
+    fs::write(path, out)
+}
+
+pub fn read_shard(path: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+    let bytes = fs::read(&path)?;
+    let name = path.as_ref().display();
+
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(invalid_data(format!("{}: not an npy file", name)));
+    }
+
+    // version 1 uses a u16 header length, versions 2 and 3 a u32
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        2 | 3 if bytes.len() >= 12 => (
+            12,
+            u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize,
+        ),
+        v => {
+            return Err(invalid_data(format!(
+                "{}: unsupported npy version {}",
+                name, v
+            )))
+        }
+    };
+
+    let data_start = header_start + header_len;
+    let header = bytes
+        .get(header_start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| invalid_data(format!("{}: truncated npy header", name)))?;
+
+    if !header.contains(DESCR) || !header.contains("'fortran_order': False") {
+        return Err(invalid_data(format!(
+            "{}: unexpected npy dtype, expected {}",
+            name, DESCR
+        )));
+    }
+
+    let len = header
+        .split("'shape': (")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+        .and_then(|n| n.trim().parse::<usize>().ok())
+        .ok_or_else(|| invalid_data(format!("{}: could not read npy shape", name)))?;
+
+    let data = &bytes[data_start..];
+    if data.len() != len * RECORD_SIZE {
+        return Err(invalid_data(format!(
+            "{}: expected {} records but found {} bytes",
+            name,
+            len,
+            data.len()
+        )));
+    }
+
+    Ok(data
+        .chunks_exact(RECORD_SIZE)
+        .map(Record::read_from)
+        .collect())
+}
+
+// reads every shard in `dir`, in shard order
+pub fn read_dataset(dir: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|p| {
+        p.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("shard-") && n.ends_with(".npy"))
+    });
+    paths.sort();
+
+    let mut records = Vec::new();
+    for path in paths {
+        records.extend(read_shard(path)?);
+    }
+    Ok(records)
+}
+
+// plays one game with `policy` and writes a record for every move made;
+// returns the final board
+pub fn record_game(policy: &mut dyn Policy, writer: &mut ShardWriter) -> io::Result<Board> {
+    // returns are only known once the game ends, so keep the score before
+    // each move and fill them in afterwards
+    let mut moves: Vec<(Record, u32)> = Vec::new();
+
+    let b = policy::play_game(policy, |board, dir| {
+        let legal = policy::legal_moves(board).fold(0, |mask, m| mask | (1 << move_index(m)));
+        let record = Record {
+            board: encode_board(board),
+            action: move_index(dir) as u8,
+            legal,
+            ret: 0,
+        };
+        moves.push((record, board.get_score()));
+    });
+
+    for (mut record, score_before) in moves {
+        record.ret = b.get_score() - score_before;
+        writer.push(record)?;
+    }
+
+    Ok(b)
+}
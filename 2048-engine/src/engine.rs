@@ -1,9 +1,9 @@
 use core::fmt;
 use std::fmt::{Display, Formatter};
 
-use rand::{random, seq::SliceRandom};
+use rand::{seq::SliceRandom, Rng};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Move {
     Up,
     Down,
@@ -11,29 +11,195 @@ pub enum Move {
     Right,
 }
 
+impl Move {
+    pub const ALL: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tiles {
+    // 2 + 2 = 4, 4 + 4 = 8, ...; spawns 2s and 4s
+    #[default]
+    PowersOfTwo,
+    // consecutive Fibonacci numbers merge: 1 + 1 = 2, 1 + 2 = 3, 2 + 3 = 5, ...;
+    // spawns 1s and 2s
+    Fibonacci,
+}
+
+// rule variants; the default is standard 2048
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub tiles: Tiles,
+    // a merged tile may merge again with the next tile in the same move,
+    // so 8 4 2 2 slides right to 0 0 0 16
+    pub chain_merges: bool,
+    // bitmask of obstacle cells; they never hold a tile and split rows and
+    // columns into segments that slide independently
+    pub blocked: u16,
+    // no tile spawns after a move in a direction that changed nothing; on by
+    // default, turning it off spawns a tile after every move
+    pub no_spawn_on_moveless: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            tiles: Tiles::default(),
+            chain_merges: false,
+            blocked: 0,
+            no_spawn_on_moveless: true,
+        }
+    }
+}
+
+impl Rules {
+    pub fn is_blocked(&self, cell: usize) -> bool {
+        self.blocked & (1 << cell) != 0
+    }
+
+    #[inline]
+    pub fn can_merge(&self, a: u32, b: u32) -> bool {
+        match self.tiles {
+            Tiles::PowersOfTwo => a == b,
+            Tiles::Fibonacci => {
+                let (lo, hi) = (a.min(b), a.max(b));
+                if lo == 1 && hi == 1 {
+                    return true;
+                }
+
+                let (mut x, mut y) = (1, 2);
+                while y <= hi {
+                    if x == lo && y == hi {
+                        return true;
+                    }
+                    (x, y) = (y, x + y);
+                }
+                false
+            }
+        }
+    }
+
+    // (value, probability) of each kind of newly spawned tile
+    pub fn spawn_distribution(&self) -> [(u32, f64); 2] {
+        match self.tiles {
+            Tiles::PowersOfTwo => [(2, 0.8), (4, 0.2)],
+            Tiles::Fibonacci => [(1, 0.8), (2, 0.2)],
+        }
+    }
+
+    // the value of a spawned tile given a roll in [0, 1): the first value
+    // whose cumulative probability reaches the roll
+    fn spawn_value(&self, roll: f64) -> u32 {
+        let distribution = self.spawn_distribution();
+        let mut cumulative = 0.0;
+        for (value, prob) in distribution {
+            cumulative += prob;
+            if roll <= cumulative {
+                return value;
+            }
+        }
+        distribution[distribution.len() - 1].0
+    }
+}
+
+// a tile produced by merging two tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Merge {
+    pub cell: usize,
+    pub value: u32,
+}
+
+// where a tile that was on the board before the move ended up; both tiles
+// in a merge point `to` the cell of the merged tile with `merged` set, and
+// tiles that did not move have `from == to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TileMove {
+    pub from: usize,
+    pub to: usize,
+    pub merged: bool,
+}
+
+// the random choices behind a spawned tile: which of the empty cells it lands
+// on, counting them in row-major order, and a roll in [0, 1) for its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnDraw {
+    pub nth_empty: usize,
+    pub roll: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spawn {
+    pub cell: usize,
+    pub value: u32,
+}
+
+// everything that happened during a single call to `Board::make_move`;
+// cells are indices into the row-major board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MoveOutcome {
+    pub changed: bool,
+    pub score_gained: u32,
+    pub spawned: Option<Spawn>,
+    pub game_over: bool,
+    // at most three merges per row (with chain merges); kept inline so
+    // moves don't allocate
+    merges: [Merge; 12],
+    num_merges: usize,
+    // bitmask of the cells whose tiles ended up in each cell
+    sources: [u16; 16],
+}
+
+impl MoveOutcome {
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges[..self.num_merges]
+    }
+
+    // one entry per tile that was on the board before the move; built on
+    // demand so moves that are never animated don't pay for it
+    pub fn tile_moves(&self) -> impl Iterator<Item = TileMove> + '_ {
+        self.sources.iter().enumerate().flat_map(|(to, sources)| {
+            let merged = sources.count_ones() > 1;
+            (0..16)
+                .filter(move |from| sources & (1 << from) != 0)
+                .map(move |from| TileMove { from, to, merged })
+        })
+    }
+
+    fn push_merge(&mut self, cell: usize, value: u32) {
+        self.merges[self.num_merges] = Merge { cell, value };
+        self.num_merges += 1;
+        self.score_gained += value;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Board {
     board: [u32; 16],
     move_num: u32,
     score: u32,
     game_over: bool,
+    rules: Rules,
 }
 
 impl Board {
     pub fn new() -> Self {
+        Self::with_rules(Rules::default())
+    }
+
+    pub fn with_rules(rules: Rules) -> Self {
+        Self::with_rng(rules, &mut rand::thread_rng())
+    }
+
+    // a new game whose starting tiles are drawn from `rng`
+    pub fn with_rng(rules: Rules, rng: &mut impl Rng) -> Self {
         let mut board = [0; 16];
 
-        let mut numbers: Vec<usize> = (0..16).collect();
-        assert!(numbers.len() == 16);
-        numbers.shuffle(&mut rand::thread_rng());
+        let mut numbers: Vec<usize> = (0..16).filter(|i| !rules.is_blocked(*i)).collect();
+        assert!(numbers.len() >= 2, "need at least two open cells");
+        numbers.shuffle(rng);
 
         // place two random numbers on the board
         for i in 0..2 {
-            if random::<f64>() > 0.8 {
-                board[numbers[i]] = 4
-            } else {
-                board[numbers[i]] = 2;
-            }
+            board[numbers[i]] = rules.spawn_value(rng.gen());
         }
 
         Self {
@@ -41,75 +207,138 @@ impl Board {
             move_num: 0,
             score: 0,
             game_over: false,
+            rules,
+        }
+    }
+
+    // a board with the given tiles and no moves made
+    pub fn from_board(board: [u32; 16]) -> Self {
+        Self::from_board_with_rules(board, Rules::default())
+    }
+
+    pub fn from_board_with_rules(board: [u32; 16], rules: Rules) -> Self {
+        for cell in (0..16).filter(|i| rules.is_blocked(*i)) {
+            assert!(board[cell] == 0, "blocked cell {} holds a tile", cell);
         }
+
+        let mut b = Self {
+            board,
+            move_num: 0,
+            score: 0,
+            game_over: false,
+            rules,
+        };
+        b.game_over = b.is_game_over();
+        b
+    }
+
+    // places a tile on an empty cell, as if it had spawned there
+    pub fn place_tile(&mut self, cell: usize, value: u32) {
+        assert!(self.is_empty_cell(cell), "cell {} is not empty", cell);
+        self.board[cell] = value;
+    }
+
+    pub fn is_empty_cell(&self, i: usize) -> bool {
+        self.board[i] == 0 && !self.rules.is_blocked(i)
+    }
+
+    pub fn make_move(&mut self, dir: Move) -> MoveOutcome {
+        self.make_move_with_rng(dir, &mut rand::thread_rng())
     }
 
-    // returns boolean tuple (board_changed, game_over)
-    pub fn make_move(&mut self, dir: Move) -> (bool, bool) {
+    // like `make_move`, drawing the spawned tile from `rng`
+    pub fn make_move_with_rng(&mut self, dir: Move, rng: &mut impl Rng) -> MoveOutcome {
+        self.make_move_with_draw(dir, |num_empty| SpawnDraw {
+            nth_empty: rng.gen_range(0..num_empty),
+            roll: rng.gen(),
+        })
+    }
+
+    // like `make_move`, with `draw` choosing the spawned tile given the number
+    // of empty cells; it is only called when a tile spawns
+    pub fn make_move_with_draw(
+        &mut self,
+        dir: Move,
+        draw: impl FnOnce(usize) -> SpawnDraw,
+    ) -> MoveOutcome {
         self.move_num += 1;
 
-        let (board_changed, score_increase) = Self::_make_move(&mut self.board, dir);
-        self.score += score_increase;
+        let mut outcome = Self::_make_move(&mut self.board, &self.rules, dir);
+        self.score += outcome.score_gained;
 
-        // add a random 2 or 4 to the board
-        let zero_pos: Vec<usize> = (0..16 as usize).filter(|i| self.board[*i] == 0).collect();
-        if zero_pos.len() != 0 && board_changed {
-            let random_zero = zero_pos.choose(&mut rand::thread_rng()).unwrap();
+        // add a random 2 or 4 (or 1 or 2 for fibonacci) to the board
+        let num_zeros = (0..16).filter(|i| self.is_empty_cell(*i)).count();
+        if num_zeros != 0 && (outcome.changed || !self.rules.no_spawn_on_moveless) {
+            let draw = draw(num_zeros);
+            let random_zero = (0..16)
+                .filter(|i| self.is_empty_cell(*i))
+                .nth(draw.nth_empty)
+                .expect("spawn drawn outside the empty cells");
 
-            if random::<f64>() > 0.8 {
-                self.board[*random_zero] = 4;
-            } else {
-                self.board[*random_zero] = 2;
-            }
+            self.board[random_zero] = self.rules.spawn_value(draw.roll);
+
+            outcome.spawned = Some(Spawn {
+                cell: random_zero,
+                value: self.board[random_zero],
+            });
         }
 
-        if zero_pos.len() == 1 && board_changed {
+        if num_zeros == 1 && outcome.spawned.is_some() {
             // just placed last square; check if game over
             self.game_over = self.is_game_over();
         }
 
-        (board_changed, self.game_over)
+        outcome.game_over = self.game_over;
+        outcome
+    }
+
+    // returns the board after sliding in `dir` without spawning a new tile,
+    // or None if the move would not change the board
+    pub fn peek_move(&self, dir: Move) -> Option<Board> {
+        let mut next = *self;
+        let outcome = Self::_make_move(&mut next.board, &self.rules, dir);
+
+        if !outcome.changed {
+            return None;
+        }
+
+        next.score += outcome.score_gained;
+        Some(next)
     }
 
-    fn _make_move(board: &mut [u32; 16], dir: Move) -> (bool, u32) {
-        let results: Vec<(bool, u32)> = (0..4)
-            .map(|i| Self::handle_row(board, Self::make_idx_func(dir, i)))
-            .collect();
+    fn _make_move(board: &mut [u32; 16], rules: &Rules, dir: Move) -> MoveOutcome {
+        let mut outcome = MoveOutcome::default();
 
-        (
-            results.iter().any(|e| e.0),
-            results.iter().map(|e| e.1).sum(),
-        )
+        for i in 0..4 {
+            Self::handle_row(board, rules, Self::make_idx_func(dir, i), &mut outcome);
+        }
+
+        outcome
     }
 
     fn make_idx_func(dir: Move, i: usize) -> impl Fn(usize) -> usize {
-        assert!(i < 4);
+        debug_assert!(i < 4);
 
-        let func = move |j: usize| -> usize {
-            assert!(j < 4);
+        move |j: usize| -> usize {
+            debug_assert!(j < 4);
             match dir {
                 Move::Up => 4 * (3 - j) + i,
                 Move::Down => 4 * j + i,
                 Move::Left => 4 * i + (3 - j),
                 Move::Right => 4 * i + j,
             }
-        };
-        func
+        }
     }
 
     fn is_game_over(&self) -> bool {
-        let zero_pos: Vec<usize> = (0..16 as usize).filter(|e| self.board[*e] == 0).collect();
-
-        if zero_pos.len() > 0 {
+        if (0..16).any(|i| self.is_empty_cell(i)) {
             return false;
         }
 
         let mut board_copy: [u32; 16] = [0; 16];
-        for each in [Move::Up, Move::Down, Move::Left, Move::Right] {
+        for each in Move::ALL {
             board_copy.copy_from_slice(&self.board);
-            let (board_changed, _) = Self::_make_move(&mut board_copy, each);
-
-            if board_changed {
+            if Self::_make_move(&mut board_copy, &self.rules, each).changed {
                 return false;
             }
         }
@@ -117,54 +346,154 @@ impl Board {
         true
     }
 
-    // returns (board_changed, score_increase) from operating on this specific row
-    fn handle_row(board: &mut [u32; 16], idx_func: impl Fn(usize) -> usize) -> (bool, u32) {
-        let idx: Vec<usize> = (0..4).map(idx_func).collect();
+    // slides and merges this specific row, recording any changes in `outcome`;
+    // blocked cells split the row into segments that slide independently
+    fn handle_row(
+        board: &mut [u32; 16],
+        rules: &Rules,
+        idx_func: impl Fn(usize) -> usize,
+        outcome: &mut MoveOutcome,
+    ) {
+        let idx: [usize; 4] = std::array::from_fn(idx_func);
+
+        if rules.blocked == 0 {
+            Self::handle_segment(board, rules, &idx, outcome);
+            return;
+        }
+
+        let mut start = 0;
+        for end in 0..=4 {
+            if end == 4 || rules.is_blocked(idx[end]) {
+                let segment = &idx[start..end];
+                match segment.len() {
+                    0 => {}
+                    1 => Self::handle_segment::<1>(
+                        board,
+                        rules,
+                        segment.try_into().unwrap(),
+                        outcome,
+                    ),
+                    2 => Self::handle_segment::<2>(
+                        board,
+                        rules,
+                        segment.try_into().unwrap(),
+                        outcome,
+                    ),
+                    3 => Self::handle_segment::<3>(
+                        board,
+                        rules,
+                        segment.try_into().unwrap(),
+                        outcome,
+                    ),
+                    _ => Self::handle_segment::<4>(
+                        board,
+                        rules,
+                        segment.try_into().unwrap(),
+                        outcome,
+                    ),
+                }
+                start = end + 1;
+            }
+        }
+    }
 
+    // generic over the segment length so the common unblocked case is a
+    // fixed-size loop the compiler can unroll
+    fn handle_segment<const N: usize>(
+        board: &mut [u32; 16],
+        rules: &Rules,
+        idx: &[usize; N],
+        outcome: &mut MoveOutcome,
+    ) {
         let row_sum: u32 = idx.iter().map(|e| board[*e]).sum();
         if row_sum == 0 {
-            return (false, 0);
+            return;
         }
         let mut board_changed = false;
 
+        // bitmask of the cells each tile in the segment started in, moved
+        // along with the tile; merged tiles have more than one bit set
+        let mut sources = [0u16; N];
+        for i in 0..N {
+            if board[idx[i]] != 0 {
+                sources[i] = 1 << idx[i];
+            }
+        }
+
         // move everything to the right
         let mut shift_right = 0;
-        for i in (0..4).rev() {
+        for i in (0..N).rev() {
             if board[idx[i]] == 0 {
                 shift_right += 1;
             } else if shift_right != 0 {
                 board_changed = true;
                 board[idx[i + shift_right]] = board[idx[i]];
                 board[idx[i]] = 0;
+                sources[i + shift_right] = sources[i];
+                sources[i] = 0;
             }
         }
 
-        let mut score_increase = 0;
-        for i in (1..4).rev() {
-            if board[idx[i]] == 0 {
+        let merges_before = outcome.num_merges;
+        let mut i = N - 1;
+        while i > 0 {
+            let (a, b) = (board[idx[i]], board[idx[i - 1]]);
+            if a == 0 {
                 break;
-            } else if board[idx[i]] == board[idx[i - 1]] {
-                board[idx[i]] *= 2;
-                score_increase += board[idx[i]];
+            } else if b != 0 && rules.can_merge(a, b) {
+                board[idx[i]] = a + b;
+                outcome.push_merge(idx[i], board[idx[i]]);
+                sources[i] |= sources[i - 1];
                 board[idx[i - 1]] = 0;
+                sources[i - 1] = 0;
                 for j in (0..(i - 1)).rev() {
                     if board[idx[j]] != 0 {
                         board[idx[j + 1]] = board[idx[j]];
                         board[idx[j]] = 0;
+                        sources[j + 1] = sources[j];
+                        sources[j] = 0;
                     }
                 }
+
+                // the merged tile stays put so it can meet the next tile
+                if rules.chain_merges {
+                    continue;
+                }
             }
+            i -= 1;
         }
 
-        assert!(row_sum == idx.iter().map(|e| board[*e]).sum());
+        // sliding and merging must conserve the row's total and leave all
+        // the zeros on the left; only checked in debug builds since this is
+        // the hot path
+        #[cfg(debug_assertions)]
+        {
+            assert!(row_sum == idx.iter().map(|e| board[*e]).sum());
 
-        let num_zeros = idx.iter().filter(|e| board[**e] == 0).count();
+            let num_zeros = idx.iter().filter(|e| board[**e] == 0).count();
 
-        for i in 0..num_zeros {
-            assert!(board[idx[i]] == 0);
+            for i in 0..num_zeros {
+                assert!(board[idx[i]] == 0);
+            }
+        }
+
+        for i in 0..N {
+            outcome.sources[idx[i]] = sources[i];
         }
 
-        (board_changed || score_increase != 0, score_increase)
+        outcome.changed |= board_changed || outcome.num_merges != merges_before;
+    }
+
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn get_board(&self) -> &[u32; 16] {
+        &self.board
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game_over
     }
 
     pub fn get_max(&self) -> u32 {
@@ -180,17 +509,27 @@ impl Board {
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
+        writeln!(
             f,
-            "--- Board (Move: {}; Score: {}) ---\n",
+            "--- Board (Move: {}; Score: {}) ---",
             self.move_num, self.score
         )?;
 
         for i in 0..4 {
             for j in 0..4 {
-                write!(f, "{:4}", self.board[4 * i + j])?;
+                if self.rules.is_blocked(4 * i + j) {
+                    write!(f, "{:>4}", "#")?;
+                } else {
+                    write!(f, "{:4}", self.board[4 * i + j])?;
+                }
             }
             writeln!(f)?;
         }
@@ -0,0 +1,368 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    engine::{Board, Move, Tiles},
+    policy,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    EmptyCells,
+    Monotonicity,
+    Smoothness,
+    MergePotential,
+    MaxInCorner,
+    Snake,
+}
+
+// snake path starting in the top left corner; lower number = heavier weight
+const SNAKE_ORDER: [u32; 16] = [0, 1, 2, 3, 7, 6, 5, 4, 8, 9, 10, 11, 15, 14, 13, 12];
+
+const CORNERS: [usize; 4] = [0, 3, 12, 15];
+
+// how many merges it takes to build the tile, plus one, with empty cells as 0:
+// log2 of the value for powers of two, or the position in 1, 2, 3, 5, 8, ...
+// for Fibonacci tiles
+fn rank(v: u32, tiles: Tiles) -> f64 {
+    if v == 0 {
+        return 0.0;
+    }
+
+    match tiles {
+        Tiles::PowersOfTwo => v.trailing_zeros() as f64,
+        Tiles::Fibonacci => {
+            let (mut rank, mut x, mut y) = (1, 1, 2);
+            while x < v {
+                (x, y) = (y, x + y);
+                rank += 1;
+            }
+            rank as f64
+        }
+    }
+}
+
+// indices of the 4 rows followed by the 4 columns
+fn lines() -> impl Iterator<Item = [usize; 4]> {
+    (0..4)
+        .map(|i| [4 * i, 4 * i + 1, 4 * i + 2, 4 * i + 3])
+        .chain((0..4).map(|i| [i, i + 4, i + 8, i + 12]))
+}
+
+impl Heuristic {
+    pub const ALL: [Heuristic; 6] = [
+        Heuristic::EmptyCells,
+        Heuristic::Monotonicity,
+        Heuristic::Smoothness,
+        Heuristic::MergePotential,
+        Heuristic::MaxInCorner,
+        Heuristic::Snake,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Heuristic::EmptyCells => "empty",
+            Heuristic::Monotonicity => "monotonicity",
+            Heuristic::Smoothness => "smoothness",
+            Heuristic::MergePotential => "merges",
+            Heuristic::MaxInCorner => "corner",
+            Heuristic::Snake => "snake",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|h| h.name() == name)
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|h| h == self).unwrap()
+    }
+
+    pub fn eval(&self, board: &Board) -> f64 {
+        let cells = board.get_board();
+        let rules = board.get_rules();
+        let rank = |v: u32| rank(v, rules.tiles);
+
+        match self {
+            Heuristic::EmptyCells => (0..16).filter(|i| board.is_empty_cell(*i)).count() as f64,
+            Heuristic::Monotonicity => {
+                // penalize each row/column by how far it is from being sorted
+                // in its better direction
+                lines()
+                    .map(|line| {
+                        let (mut inc, mut dec) = (0.0, 0.0);
+                        for j in 0..3 {
+                            let diff = rank(cells[line[j + 1]]) - rank(cells[line[j]]);
+                            if diff > 0.0 {
+                                dec += diff;
+                            } else {
+                                inc -= diff;
+                            }
+                        }
+                        -f64::min(inc, dec)
+                    })
+                    .sum()
+            }
+            Heuristic::Smoothness => {
+                let mut penalty = 0.0;
+                for line in lines() {
+                    for j in 0..3 {
+                        let (a, b) = (cells[line[j]], cells[line[j + 1]]);
+                        if a != 0 && b != 0 {
+                            penalty += (rank(a) - rank(b)).abs();
+                        }
+                    }
+                }
+                -penalty
+            }
+            Heuristic::MergePotential => {
+                let mut merges = 0;
+                for line in lines() {
+                    // compare neighbours after empty cells are squeezed out;
+                    // tiles on either side of a blocked cell never meet
+                    let mut prev = None;
+                    for i in line {
+                        if rules.is_blocked(i) {
+                            prev = None;
+                        } else if cells[i] != 0 {
+                            if prev.is_some_and(|p| rules.can_merge(p, cells[i])) {
+                                merges += 1;
+                            }
+                            prev = Some(cells[i]);
+                        }
+                    }
+                }
+                merges as f64
+            }
+            Heuristic::MaxInCorner => {
+                let max = board.get_max();
+                if CORNERS.iter().any(|i| cells[*i] == max) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Heuristic::Snake => cells
+                .iter()
+                .zip(SNAKE_ORDER)
+                .map(|(v, order)| rank(*v) * 0.5_f64.powi(order as i32))
+                .sum(),
+        }
+    }
+}
+
+impl Display for Heuristic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// weighted sum of heuristics; weights are stored in `Heuristic::ALL` order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluator {
+    weights: [f64; 6],
+}
+
+impl Evaluator {
+    pub fn new(weights: [f64; 6]) -> Self {
+        Self { weights }
+    }
+
+    pub fn weight(&self, h: Heuristic) -> f64 {
+        self.weights[h.index()]
+    }
+
+    pub fn set_weight(&mut self, h: Heuristic, weight: f64) {
+        self.weights[h.index()] = weight;
+    }
+
+    pub fn weights(&self) -> &[f64; 6] {
+        &self.weights
+    }
+
+    pub fn eval(&self, board: &Board) -> f64 {
+        Heuristic::ALL
+            .iter()
+            .zip(self.weights)
+            .filter(|(_, w)| *w != 0.0)
+            .map(|(h, w)| w * h.eval(board))
+            .sum()
+    }
+
+    // one-ply greedy search: the move whose resulting board (before the
+    // random tile spawns) has the highest score gain + evaluation
+    pub fn best_move(&self, board: &Board) -> Option<Move> {
+        let mut best: Option<(Move, f64)> = None;
+
+        for dir in Move::ALL {
+            if let Some(next) = board.peek_move(dir) {
+                let value = (next.get_score() - board.get_score()) as f64 + self.eval(&next);
+                if best.is_none_or(|(_, v)| value > v) {
+                    best = Some((dir, value));
+                }
+            }
+        }
+
+        best.map(|(dir, _)| dir)
+    }
+
+    // parses weights in the form `{name} {weight}`, one per line; `#` starts a comment
+    // and heuristics that are not mentioned get a weight of 0
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut evaluator = Self::new([0.0; 6]);
+
+        for (line_num, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (name, weight) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(name), Some(weight), None) => (name, weight),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected `{{name}} {{weight}}`",
+                        line_num + 1
+                    ))
+                }
+            };
+
+            let h = Heuristic::from_name(name)
+                .ok_or_else(|| format!("line {}: unknown heuristic `{}`", line_num + 1, name))?;
+            let weight = weight
+                .parse::<f64>()
+                .map_err(|_| format!("line {}: invalid weight `{}`", line_num + 1, weight))?;
+
+            evaluator.set_weight(h, weight);
+        }
+
+        Ok(evaluator)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new([2.7, 1.0, 0.1, 1.0, 1.0, 0.0])
+    }
+}
+
+impl Display for Evaluator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (h, w) in Heuristic::ALL.iter().zip(self.weights) {
+            writeln!(f, "{} {}", h, w)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Strategy {
+    // sample every weight uniformly from [0, max_weight] each iteration
+    RandomSearch { max_weight: f64 },
+    // (1+1)-ES: perturb the best weights with gaussian noise of size `step`,
+    // growing the step on improvement and shrinking it otherwise
+    HillClimb { step: f64 },
+}
+
+pub struct Tuner {
+    strategy: Strategy,
+    iterations: usize,
+    games_per_eval: usize,
+    // the same seed tunes to the same weights
+    seed: u64,
+}
+
+impl Tuner {
+    pub fn new(
+        strategy: Strategy,
+        iterations: usize,
+        games_per_eval: usize,
+        seed: u64,
+    ) -> Result<Self, String> {
+        if games_per_eval == 0 {
+            return Err("games_per_eval must be at least 1".to_string());
+        }
+
+        Ok(Self {
+            strategy,
+            iterations,
+            games_per_eval,
+            seed,
+        })
+    }
+
+    // average final score of `games_per_eval` games played with `evaluator`;
+    // the games' tiles are drawn from `seed`, so evaluators scored with the
+    // same seed start from the same boards
+    pub fn fitness(&self, evaluator: &Evaluator, seed: u64) -> f64 {
+        let total: u64 = (0..self.games_per_eval as u64)
+            .map(|i| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i));
+                policy::play_game_with_rng(&mut evaluator.clone(), &mut rng, |_, _| {}).get_score()
+                    as u64
+            })
+            .sum();
+        total as f64 / self.games_per_eval as f64
+    }
+
+    // returns the best evaluator found along with its fitness on the last
+    // games it played
+    pub fn tune(&self, start: &Evaluator) -> (Evaluator, f64) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut best = start.clone();
+        let mut best_fitness = None;
+        let mut strategy = self.strategy;
+
+        for _ in 0..self.iterations {
+            let candidate = match strategy {
+                Strategy::RandomSearch { max_weight } => {
+                    Evaluator::new(std::array::from_fn(|_| rng.gen_range(0.0..=max_weight)))
+                }
+                Strategy::HillClimb { step } => Evaluator::new(std::array::from_fn(|i| {
+                    best.weights[i] + step * gaussian(&mut rng)
+                })),
+            };
+
+            // the best evaluator plays the candidate's games again rather than
+            // keeping its old score, so one lucky run can't stay on top
+            let games = rng.gen();
+            let fitness = self.fitness(&candidate, games);
+            let incumbent = self.fitness(&best, games);
+            let improved = fitness > incumbent;
+            if improved {
+                best = candidate;
+            }
+            best_fitness = Some(fitness.max(incumbent));
+
+            if let Strategy::HillClimb { step } = &mut strategy {
+                *step *= if improved { 1.5 } else { 0.9 };
+            }
+        }
+
+        // with no iterations the start still gets scored once
+        let best_fitness = best_fitness.unwrap_or_else(|| self.fitness(&best, rng.gen()));
+        (best, best_fitness)
+    }
+}
+
+// standard normal sample via Box-Muller
+fn gaussian<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
@@ -0,0 +1,7 @@
+pub mod array;
+pub mod conformance;
+pub mod dataset;
+pub mod engine;
+pub mod heuristics;
+pub mod policy;
+pub mod search;
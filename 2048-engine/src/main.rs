@@ -1,11 +1,154 @@
-pub mod array;
-pub mod engine;
+use std::{collections::HashMap, io, time::Duration};
 
-use std::{collections::HashMap, io};
+use engine2048::{
+    array, conformance, dataset, engine,
+    heuristics::Evaluator,
+    policy::{Policy, RandomPolicy},
+    search::TimedSearch,
+};
 
 use rand::random;
 
+const USAGE: &str = "usage: engine2048 [export <out_dir> <games> <random|greedy|search:<ms>|weights_file> [shard_size] \
+                     | conformance <out_file> [games] [seed] | play <ms_per_move>]";
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None => run_basic_strat_stats(),
+        Some("export") => {
+            if let Err(e) = export_dataset(&args[1..]) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some("play") => {
+            if let Err(e) = play_timed(&args[1..]) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some("conformance") => {
+            if let Err(e) = write_conformance_cases(&args[1..]) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
+
+// plays games with the given policy and writes (board, move, legal moves, return)
+// records for imitation learning
+fn export_dataset(args: &[String]) -> Result<(), String> {
+    let (out_dir, games, strategy) = match args {
+        [out_dir, games, strategy] | [out_dir, games, strategy, _] => (out_dir, games, strategy),
+        _ => return Err(USAGE.to_string()),
+    };
+    let games: usize = games.parse().map_err(|_| USAGE.to_string())?;
+    let shard_size: usize = match args.get(3) {
+        Some(n) => match n.parse() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(USAGE.to_string()),
+        },
+        None => 100_000,
+    };
+
+    let mut policy: Box<dyn Policy> = match strategy.as_str() {
+        "random" => Box::new(RandomPolicy),
+        "greedy" => Box::new(Evaluator::default()),
+        s if s.starts_with("search:") => {
+            let ms: u64 = s["search:".len()..]
+                .parse()
+                .map_err(|_| USAGE.to_string())?;
+            Box::new(TimedSearch::new(
+                Evaluator::default(),
+                Duration::from_millis(ms),
+            ))
+        }
+        path => Box::new(Evaluator::load(path).map_err(|e| format!("{}: {}", path, e))?),
+    };
+
+    let mut writer = dataset::ShardWriter::new(out_dir, shard_size).map_err(|e| e.to_string())?;
+    let mut total_score: u64 = 0;
+    for i in 0..games {
+        if i % 200 == 0 {
+            println!("Game: {}", i);
+        }
+
+        let b = dataset::record_game(policy.as_mut(), &mut writer).map_err(|e| e.to_string())?;
+        total_score += b.get_score() as u64;
+    }
+
+    let shards = writer.finish().map_err(|e| e.to_string())?;
+    println!(
+        "Wrote {} shards to {} (avg score: {:.1})",
+        shards,
+        out_dir,
+        total_score as f64 / games.max(1) as f64
+    );
+
+    Ok(())
+}
+
+// plays a single game with a fixed time budget per move, reporting how deep
+// each search got
+fn play_timed(args: &[String]) -> Result<(), String> {
+    let ms: u64 = match args {
+        [ms] => ms.parse().map_err(|_| USAGE.to_string())?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut search = TimedSearch::new(Evaluator::default(), Duration::from_millis(ms));
+    let mut b = engine::Board::new();
+
+    while let Some(dir) = search.choose_move(&b) {
+        let result = search.last_result().unwrap();
+        println!(
+            "Move {}: {:?} (depth: {}; nodes: {}; {:?})",
+            b.num_moves() + 1,
+            dir,
+            result.depth,
+            result.nodes,
+            result.elapsed
+        );
+
+        if b.make_move(dir).game_over {
+            break;
+        }
+    }
+
+    println!("{}", b);
+    Ok(())
+}
+
+// regenerates the language-neutral corpus of slide and spawn cases that other
+// rule implementations are checked against
+fn write_conformance_cases(args: &[String]) -> Result<(), String> {
+    let (out_file, games, seed) = match args {
+        [out_file] => (out_file, 10, 0),
+        [out_file, games] => (out_file, games.parse().map_err(|_| USAGE.to_string())?, 0),
+        [out_file, games, seed] => (
+            out_file,
+            games.parse().map_err(|_| USAGE.to_string())?,
+            seed.parse().map_err(|_| USAGE.to_string())?,
+        ),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let cases = conformance::generate(games, 20, seed);
+    let lines: Vec<String> = cases.iter().map(|c| c.to_json()).collect();
+    std::fs::write(out_file, lines.join("\n") + "\n").map_err(|e| e.to_string())?;
+
+    println!("Wrote {} cases to {}", cases.len(), out_file);
+    Ok(())
+}
+
+fn run_basic_strat_stats() {
     let iters = 1000000;
 
     let mut total_moves = 0;
@@ -25,7 +168,7 @@ fn main() {
     println!("Total Moves: {}", total_moves);
 
     let mr = array::Tensor::new(max_reached);
-    let sr = array::Tensor::new(score_reached);
+    let _sr = array::Tensor::new(score_reached);
 
     println!("{:?}", mr.unique());
 
@@ -35,34 +178,32 @@ fn main() {
 fn run_basic_strat() -> engine::Board {
     let mut b = engine::Board::new();
 
-    let mut board_changed: bool;
     let mut game_over = false;
-    // let (board_changed, game_over) = (true, false);
 
     while !game_over {
         let move_ = random::<bool>() as usize;
-
-        (board_changed, game_over) = b.make_move([engine::Move::Down, engine::Move::Right][move_]);
-
-        // println!(b);
-
-        if !board_changed && !game_over {
-            (board_changed, game_over) =
-                b.make_move([engine::Move::Down, engine::Move::Right][(move_ + 1) % 2]);
-
-            if !board_changed && !game_over {
-                (board_changed, game_over) = b.make_move(engine::Move::Up);
-
-                if !board_changed && !game_over {
-                    (_, game_over) = b.make_move(engine::Move::Left);
-                }
+        let order = [
+            [engine::Move::Down, engine::Move::Right][move_],
+            [engine::Move::Down, engine::Move::Right][(move_ + 1) % 2],
+            engine::Move::Up,
+            engine::Move::Left,
+        ];
+
+        // fall through to the next move only if the previous one did nothing
+        for dir in order {
+            let outcome = b.make_move(dir);
+            game_over = outcome.game_over;
+
+            if outcome.changed || game_over {
+                break;
             }
         }
     }
 
-    return b;
+    b
 }
 
+#[allow(dead_code)]
 fn start_cli() {
     let mut b = engine::Board::new();
 
@@ -84,7 +225,7 @@ fn start_cli() {
         if map.contains_key(input.trim()) {
             let dir = map[input.trim()];
 
-            let (_, game_over) = b.make_move(dir);
+            let game_over = b.make_move(dir).game_over;
 
             // clear the terminal screen
             print!("{}[2J", 27 as char);
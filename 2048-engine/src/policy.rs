@@ -0,0 +1,58 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    engine::{Board, Move, Rules},
+    heuristics::Evaluator,
+};
+
+// anything that can pick the next move for a board; returns None when no
+// move changes the board
+pub trait Policy {
+    fn choose_move(&mut self, board: &Board) -> Option<Move>;
+}
+
+// moves that change the board, in `Move::ALL` order
+pub fn legal_moves(board: &Board) -> impl Iterator<Item = Move> + '_ {
+    Move::ALL
+        .into_iter()
+        .filter(|dir| board.peek_move(*dir).is_some())
+}
+
+pub struct RandomPolicy;
+
+impl Policy for RandomPolicy {
+    fn choose_move(&mut self, board: &Board) -> Option<Move> {
+        let legal: Vec<Move> = legal_moves(board).collect();
+        legal.choose(&mut rand::thread_rng()).copied()
+    }
+}
+
+impl Policy for Evaluator {
+    fn choose_move(&mut self, board: &Board) -> Option<Move> {
+        self.best_move(board)
+    }
+}
+
+// plays a full game from a fresh board, calling `on_move` with the board
+// before each move and the move chosen
+pub fn play_game(policy: &mut dyn Policy, on_move: impl FnMut(&Board, Move)) -> Board {
+    play_game_with_rng(policy, &mut rand::thread_rng(), on_move)
+}
+
+// like `play_game`, drawing every tile that spawns from `rng`
+pub fn play_game_with_rng(
+    policy: &mut dyn Policy,
+    rng: &mut impl Rng,
+    mut on_move: impl FnMut(&Board, Move),
+) -> Board {
+    let mut b = Board::with_rng(Rules::default(), rng);
+
+    while let Some(dir) = policy.choose_move(&b) {
+        on_move(&b, dir);
+        if b.make_move_with_rng(dir, rng).game_over {
+            break;
+        }
+    }
+
+    b
+}
@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    engine::{Board, Move},
+    heuristics::Evaluator,
+    policy::Policy,
+};
+
+// keeps lost positions below any position that is still alive, since the
+// heuristics themselves can be negative
+const GAME_OVER_PENALTY: f64 = 1e6;
+
+// how many nodes to expand between checks of the clock
+const NODES_PER_CLOCK_CHECK: u64 = 256;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    // deepest search (in moves) that finished before the deadline
+    pub depth: u32,
+    // nodes expanded across all depths, including an abandoned last one
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+// Expectimax over the evaluator's heuristics, deepened one move at a time
+// until `budget` runs out. Depth 1 always runs to completion so a move is
+// returned even with a tiny budget.
+pub struct TimedSearch {
+    pub evaluator: Evaluator,
+    pub budget: Duration,
+    pub max_depth: u32,
+    last_result: Option<SearchResult>,
+}
+
+// state for a single search at a fixed depth
+struct Searcher<'a> {
+    evaluator: &'a Evaluator,
+    deadline: Option<Instant>,
+    nodes: u64,
+    next_clock_check: u64,
+}
+
+impl Searcher<'_> {
+    fn timed_out(&mut self) -> bool {
+        self.nodes += 1;
+        match self.deadline {
+            Some(deadline) if self.nodes >= self.next_clock_check => {
+                self.next_clock_check = self.nodes + NODES_PER_CLOCK_CHECK;
+                Instant::now() >= deadline
+            }
+            _ => false,
+        }
+    }
+
+    fn leaf_value(&self, board: &Board) -> f64 {
+        board.get_score() as f64 + self.evaluator.eval(board)
+    }
+
+    // best value over the player's moves; None if the deadline passed
+    fn max_node(&mut self, board: &Board, depth: u32) -> Option<(Option<Move>, f64)> {
+        if self.timed_out() {
+            return None;
+        }
+
+        let mut best: Option<(Move, f64)> = None;
+        for dir in Move::ALL {
+            if let Some(next) = board.peek_move(dir) {
+                let value = self.chance_node(&next, depth - 1)?;
+                if best.is_none_or(|(_, v)| value > v) {
+                    best = Some((dir, value));
+                }
+            }
+        }
+
+        Some(match best {
+            Some((dir, value)) => (Some(dir), value),
+            None => (None, board.get_score() as f64 - GAME_OVER_PENALTY),
+        })
+    }
+
+    // expected value over every tile that could spawn on `board`
+    fn chance_node(&mut self, board: &Board, depth: u32) -> Option<f64> {
+        if depth == 0 {
+            self.nodes += 1;
+            return Some(self.leaf_value(board));
+        }
+        if self.timed_out() {
+            return None;
+        }
+
+        let num_empty = (0..16).filter(|i| board.is_empty_cell(*i)).count();
+        if num_empty == 0 {
+            return Some(self.leaf_value(board));
+        }
+
+        let mut expected = 0.0;
+        for cell in (0..16).filter(|i| board.is_empty_cell(*i)) {
+            for (value, prob) in board.get_rules().spawn_distribution() {
+                let mut next = *board;
+                next.place_tile(cell, value);
+                expected += prob * self.max_node(&next, depth)?.1;
+            }
+        }
+
+        Some(expected / num_empty as f64)
+    }
+}
+
+impl TimedSearch {
+    pub fn new(evaluator: Evaluator, budget: Duration) -> Self {
+        Self {
+            evaluator,
+            budget,
+            max_depth: 20,
+            last_result: None,
+        }
+    }
+
+    pub fn search(&self, board: &Board) -> SearchResult {
+        let start = Instant::now();
+        let deadline = start + self.budget;
+
+        let mut result = SearchResult {
+            best_move: None,
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        for depth in 1..=self.max_depth {
+            let mut searcher = Searcher {
+                evaluator: &self.evaluator,
+                deadline: if depth == 1 { None } else { Some(deadline) },
+                nodes: 0,
+                next_clock_check: NODES_PER_CLOCK_CHECK,
+            };
+
+            let found = searcher.max_node(board, depth);
+            result.nodes += searcher.nodes;
+
+            match found {
+                Some((best_move, _)) => {
+                    result.best_move = best_move;
+                    result.depth = depth;
+                }
+                None => break,
+            }
+
+            // no moves left, so searching deeper cannot change anything
+            if result.best_move.is_none() || Instant::now() >= deadline {
+                break;
+            }
+        }
+
+        result.elapsed = start.elapsed();
+        result
+    }
+
+    // the result of the most recent `choose_move` call
+    pub fn last_result(&self) -> Option<&SearchResult> {
+        self.last_result.as_ref()
+    }
+}
+
+impl Policy for TimedSearch {
+    fn choose_move(&mut self, board: &Board) -> Option<Move> {
+        let result = self.search(board);
+        self.last_result = Some(result);
+        result.best_move
+    }
+}
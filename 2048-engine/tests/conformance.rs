@@ -0,0 +1,107 @@
+use engine2048::{
+    conformance::{move_from_name, Case, SpawnCase},
+    engine::{Board, Spawn, SpawnDraw},
+};
+use serde_json::Value;
+
+const CASES: &str = include_str!("../conformance/cases.jsonl");
+
+fn cells(value: &Value) -> [u32; 16] {
+    let cells: Vec<u32> = value
+        .as_array()
+        .expect("board should be an array")
+        .iter()
+        .map(|v| v.as_u64().expect("cells should be integers") as u32)
+        .collect();
+    cells.try_into().expect("boards should have 16 cells")
+}
+
+fn spawn_case(v: &Value) -> Option<SpawnCase> {
+    let spawn = v.get("spawn")?;
+    Some(SpawnCase {
+        draw: SpawnDraw {
+            nth_empty: spawn["nth_empty"].as_u64().unwrap() as usize,
+            roll: spawn["roll"].as_f64().unwrap(),
+        },
+        spawned: match &v["spawned"] {
+            Value::Null => None,
+            s => Some(Spawn {
+                cell: s["cell"].as_u64().unwrap() as usize,
+                value: s["value"].as_u64().unwrap() as u32,
+            }),
+        },
+        game_over: v["game_over"].as_bool().unwrap(),
+    })
+}
+
+fn load_cases() -> Vec<Case> {
+    CASES
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let v: Value = serde_json::from_str(line).expect("invalid json in corpus");
+            Case {
+                board: cells(&v["board"]),
+                dir: move_from_name(v["move"].as_str().unwrap()).expect("invalid move"),
+                after: cells(&v["after"]),
+                score: v["score"].as_u64().unwrap() as u32,
+                changed: v["changed"].as_bool().unwrap(),
+                spawn: spawn_case(&v),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn engine_matches_corpus() {
+    let cases = load_cases();
+    assert!(!cases.is_empty());
+
+    for (i, case) in cases.iter().enumerate() {
+        let expected = match &case.spawn {
+            None => Case::new(case.board, case.dir),
+            Some(spawn) => Case::with_spawn(case.board, case.dir, spawn.draw),
+        };
+        assert_eq!(
+            &expected,
+            case,
+            "case {} (line {}) does not match the corpus",
+            i,
+            i + 1
+        );
+    }
+}
+
+#[test]
+fn make_move_agrees_with_corpus() {
+    // make_move should slide exactly like the corpus before spawning a tile
+    for case in load_cases().into_iter().filter(|c| c.spawn.is_none()) {
+        let mut b = Board::from_board(case.board);
+        let outcome = b.make_move(case.dir);
+
+        assert_eq!(outcome.changed, case.changed);
+        assert_eq!(outcome.score_gained, case.score);
+
+        let mut board = *b.get_board();
+        if let Some(spawn) = outcome.spawned {
+            assert_eq!(case.after[spawn.cell], 0);
+            board[spawn.cell] = 0;
+        }
+        assert_eq!(board, case.after);
+    }
+}
+
+#[test]
+fn corpus_covers_spawns_and_game_over() {
+    let spawns: Vec<SpawnCase> = load_cases().into_iter().filter_map(|c| c.spawn).collect();
+
+    let values: Vec<u32> = spawns
+        .iter()
+        .filter_map(|s| s.spawned)
+        .map(|s| s.value)
+        .collect();
+    assert!(values.contains(&2) && values.contains(&4));
+    assert!(spawns.iter().any(|s| s.spawned.is_none()));
+    assert!(spawns.iter().any(|s| s.game_over));
+    assert!(spawns.iter().any(|s| s.spawned.is_some() && !s.game_over));
+}
@@ -0,0 +1,108 @@
+use std::{fs, path::PathBuf};
+
+use engine2048::dataset::{read_dataset, read_shard, Record, ShardWriter};
+
+// a fresh directory for one test's shards
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("engine2048-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn record(i: u8) -> Record {
+    Record {
+        board: std::array::from_fn(|cell| (cell as u8 + i) % 12),
+        action: i % 4,
+        legal: i % 16,
+        ret: 1000 * i as u32 + 7,
+    }
+}
+
+#[test]
+fn shards_read_back_as_written() {
+    let dir = temp_dir("round-trip");
+    let records: Vec<Record> = (0..5).map(record).collect();
+
+    let mut writer = ShardWriter::new(&dir, 2).unwrap();
+    for r in &records {
+        writer.push(*r).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 3);
+
+    assert_eq!(
+        read_shard(dir.join("shard-00000.npy")).unwrap(),
+        records[..2]
+    );
+    assert_eq!(
+        read_shard(dir.join("shard-00002.npy")).unwrap(),
+        records[4..]
+    );
+    assert_eq!(read_dataset(&dir).unwrap(), records);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn shards_use_the_npy_layout_numpy_expects() {
+    let dir = temp_dir("layout");
+    let mut writer = ShardWriter::new(&dir, 10).unwrap();
+    for i in 0..3 {
+        writer.push(record(i)).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let bytes = fs::read(dir.join("shard-00000.npy")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // magic, version 1.0 and the little-endian header length
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert_eq!(
+        header.trim_end(),
+        "{'descr': [('board', '|u1', (16,)), ('action', '|u1'), ('legal', '|u1'), \
+         ('return', '<u4')], 'fortran_order': False, 'shape': (3,), }"
+    );
+    assert!(header.ends_with('\n'));
+
+    // 22 bytes per record: board, action, legal, then the return as a u32
+    let data = &bytes[10 + header_len..];
+    assert_eq!(data.len(), 3 * 22);
+    let r = record(2);
+    assert_eq!(&data[44..60], &r.board);
+    assert_eq!(data[60], r.action);
+    assert_eq!(data[61], r.legal);
+    assert_eq!(&data[62..66], &r.ret.to_le_bytes());
+}
+
+#[test]
+fn rejects_files_that_are_not_shards() {
+    let dir = temp_dir("invalid");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("shard-00000.npy");
+
+    fs::write(&path, b"not an npy file").unwrap();
+    assert!(read_shard(&path).is_err());
+
+    // a valid header promising more records than the file holds
+    let mut writer = ShardWriter::new(&dir, 10).unwrap();
+    writer.push(record(0)).unwrap();
+    writer.finish().unwrap();
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.pop();
+    fs::write(&path, bytes).unwrap();
+    assert!(read_shard(&path).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_empty_shards() {
+    let dir = temp_dir("empty-shards");
+    let err = ShardWriter::new(&dir, 0).err().unwrap();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!dir.exists());
+}
@@ -0,0 +1,256 @@
+use engine2048::{
+    engine::{Board, Merge, Move, Rules, Spawn, SpawnDraw, Tiles},
+    policy::{Policy, RandomPolicy},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// a board with `row` as its top row and nothing else on it
+fn top_row(row: [u32; 4]) -> Board {
+    let mut board = [0; 16];
+    board[..4].copy_from_slice(&row);
+    Board::from_board(board)
+}
+
+#[test]
+fn outcome_reports_merges_and_score() {
+    let mut b = top_row([2, 2, 4, 4]);
+    let outcome = b.make_move_with_rng(Move::Right, &mut StdRng::seed_from_u64(1));
+
+    assert!(outcome.changed);
+    assert_eq!(outcome.score_gained, 12);
+    assert_eq!(
+        outcome.merges(),
+        [Merge { cell: 3, value: 8 }, Merge { cell: 2, value: 4 }]
+    );
+    assert_eq!(b.get_score(), 12);
+    assert!(!outcome.game_over);
+}
+
+#[test]
+fn outcome_reports_the_spawned_tile() {
+    let mut b = top_row([2, 0, 0, 0]);
+    let outcome = b.make_move_with_rng(Move::Right, &mut StdRng::seed_from_u64(2));
+
+    assert!(outcome.changed);
+    assert_eq!(outcome.score_gained, 0);
+    assert!(outcome.merges().is_empty());
+
+    let Spawn { cell, value } = outcome.spawned.expect("a tile should spawn");
+    assert_ne!(cell, 3, "the slid tile is in cell 3");
+    assert!(value == 2 || value == 4);
+
+    let mut expected = [0; 16];
+    expected[3] = 2;
+    expected[cell] = value;
+    assert_eq!(b.get_board(), &expected);
+}
+
+#[test]
+fn moves_that_change_nothing_spawn_nothing() {
+    let mut b = top_row([0, 0, 2, 4]);
+    let outcome = b.make_move_with_rng(Move::Right, &mut StdRng::seed_from_u64(3));
+
+    assert!(!outcome.changed);
+    assert_eq!(outcome.score_gained, 0);
+    assert!(outcome.merges().is_empty());
+    assert_eq!(outcome.spawned, None);
+    assert_eq!(b.get_board(), top_row([0, 0, 2, 4]).get_board());
+}
+
+#[test]
+fn outcome_reports_game_over_on_the_move_that_fills_the_board() {
+    // sliding left leaves cell 3 empty for the spawn, after which nothing can
+    // merge whatever spawned
+    let mut b = Board::from_board([
+        0, 8, 16, 32, //
+        64, 128, 256, 512, //
+        8, 16, 32, 64, //
+        128, 256, 512, 1024,
+    ]);
+    let outcome = b.make_move_with_rng(Move::Left, &mut StdRng::seed_from_u64(4));
+
+    assert!(outcome.changed);
+    assert_eq!(outcome.spawned.map(|s| s.cell), Some(3));
+    assert!(outcome.game_over);
+    assert!(b.is_over());
+}
+
+#[test]
+fn replaying_tile_moves_gives_the_board_after_the_move() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut b = Board::new();
+
+    while let Some(dir) = RandomPolicy.choose_move(&b) {
+        let before = *b.get_board();
+        let outcome = b.make_move_with_rng(dir, &mut rng);
+
+        // every tile on the board before the move goes somewhere, once
+        let mut replayed = [0; 16];
+        let mut moved_from = [false; 16];
+        for tile in outcome.tile_moves() {
+            assert_ne!(before[tile.from], 0, "{:?} moves an empty cell", tile);
+            assert!(!moved_from[tile.from], "{:?} moves a tile twice", tile);
+            moved_from[tile.from] = true;
+            replayed[tile.to] += before[tile.from];
+
+            let merged = outcome.merges().iter().any(|m| m.cell == tile.to);
+            assert_eq!(tile.merged, merged, "{:?}", tile);
+        }
+        for cell in 0..16 {
+            assert_eq!(moved_from[cell], before[cell] != 0);
+        }
+
+        let mut after = *b.get_board();
+        if let Some(spawn) = outcome.spawned {
+            after[spawn.cell] = 0;
+        }
+        assert_eq!(replayed, after);
+
+        if outcome.game_over {
+            break;
+        }
+    }
+}
+
+const FIBONACCI: Rules = Rules {
+    tiles: Tiles::Fibonacci,
+    chain_merges: false,
+    blocked: 0,
+    no_spawn_on_moveless: true,
+};
+
+// the top row after sliding `row` right under `rules`, and the score gained;
+// None if nothing moved
+fn slide_right(row: [u32; 4], rules: Rules) -> Option<([u32; 4], u32)> {
+    let mut board = [0; 16];
+    board[..4].copy_from_slice(&row);
+    let next = Board::from_board_with_rules(board, rules).peek_move(Move::Right)?;
+    Some((next.get_board()[..4].try_into().unwrap(), next.get_score()))
+}
+
+#[test]
+fn fibonacci_merges_consecutive_numbers() {
+    assert_eq!(
+        slide_right([1, 1, 0, 0], FIBONACCI),
+        Some(([0, 0, 0, 2], 2))
+    );
+    assert_eq!(
+        slide_right([0, 1, 2, 0], FIBONACCI),
+        Some(([0, 0, 0, 3], 3))
+    );
+    assert_eq!(
+        slide_right([0, 0, 5, 3], FIBONACCI),
+        Some(([0, 0, 0, 8], 8))
+    );
+    assert_eq!(
+        slide_right([2, 5, 0, 0], FIBONACCI),
+        Some(([0, 0, 2, 5], 0))
+    );
+    assert_eq!(slide_right([0, 0, 1, 3], FIBONACCI), None);
+
+    // standard rules only merge equal tiles
+    assert_eq!(
+        slide_right([0, 1, 2, 0], Rules::default()),
+        Some(([0, 0, 1, 2], 0))
+    );
+}
+
+#[test]
+fn fibonacci_spawns_ones_and_twos() {
+    let mut board = [0; 16];
+    board[0] = 1;
+
+    for (roll, value) in [(0.5, 1), (0.9, 2)] {
+        let mut b = Board::from_board_with_rules(board, FIBONACCI);
+        let outcome = b.make_move_with_draw(Move::Right, |_| SpawnDraw { nth_empty: 0, roll });
+        assert_eq!(outcome.spawned, Some(Spawn { cell: 0, value }));
+    }
+
+    let b = Board::with_rng(FIBONACCI, &mut StdRng::seed_from_u64(6));
+    assert!(b.get_board().iter().all(|v| [0, 1, 2].contains(v)));
+}
+
+#[test]
+fn chain_merges_let_a_merged_tile_merge_again() {
+    let chain = Rules {
+        chain_merges: true,
+        ..Rules::default()
+    };
+    assert_eq!(slide_right([8, 4, 2, 2], chain), Some(([0, 0, 0, 16], 28)));
+    // the merged tile only meets tiles further back, so the new 4s stay apart
+    assert_eq!(slide_right([2, 2, 2, 2], chain), Some(([0, 0, 4, 4], 8)));
+
+    assert_eq!(
+        slide_right([8, 4, 2, 2], Rules::default()),
+        Some(([0, 8, 4, 4], 4))
+    );
+    assert_eq!(
+        slide_right([2, 2, 2, 2], Rules::default()),
+        Some(([0, 0, 4, 4], 8))
+    );
+}
+
+#[test]
+fn blocked_cells_split_rows() {
+    // cell 1 is blocked, so the 2s on either side never meet
+    let blocked = Rules {
+        blocked: 1 << 1,
+        ..Rules::default()
+    };
+    assert_eq!(slide_right([2, 0, 2, 0], blocked), Some(([2, 0, 0, 2], 0)));
+    assert_eq!(slide_right([2, 0, 0, 2], blocked), None);
+    assert_eq!(slide_right([0, 0, 2, 2], blocked), Some(([0, 0, 0, 4], 4)));
+
+    // nor do tiles spawn there: with cell 0 blocked the first empty cell is 1
+    let corner = Rules {
+        blocked: 1,
+        ..Rules::default()
+    };
+    let mut board = [0; 16];
+    board[4] = 2;
+    let mut b = Board::from_board_with_rules(board, corner);
+    let outcome = b.make_move_with_draw(Move::Down, |_| SpawnDraw {
+        nth_empty: 0,
+        roll: 0.0,
+    });
+    assert_eq!(outcome.spawned.map(|s| s.cell), Some(1));
+    assert!(!b.is_empty_cell(0));
+}
+
+#[test]
+#[should_panic(expected = "blocked cell 1 holds a tile")]
+fn blocked_cells_must_start_empty() {
+    let mut board = [0; 16];
+    board[1] = 2;
+    Board::from_board_with_rules(
+        board,
+        Rules {
+            blocked: 1 << 1,
+            ..Rules::default()
+        },
+    );
+}
+
+#[test]
+fn moveless_directions_spawn_only_when_the_switch_is_off() {
+    let mut board = [0; 16];
+    board[3] = 2;
+    let draw = |_| SpawnDraw {
+        nth_empty: 0,
+        roll: 0.0,
+    };
+
+    let mut b = Board::from_board(board);
+    let outcome = b.make_move_with_draw(Move::Right, draw);
+    assert!(!outcome.changed);
+    assert_eq!(outcome.spawned, None);
+
+    let spawn_always = Rules {
+        no_spawn_on_moveless: false,
+        ..Rules::default()
+    };
+    let mut b = Board::from_board_with_rules(board, spawn_always);
+    let outcome = b.make_move_with_draw(Move::Right, draw);
+    assert!(!outcome.changed);
+    assert_eq!(outcome.spawned, Some(Spawn { cell: 0, value: 2 }));
+}
@@ -0,0 +1,170 @@
+use engine2048::{
+    engine::{Board, Rules, Tiles},
+    heuristics::{Evaluator, Heuristic, Strategy, Tuner},
+};
+
+// row-major, top row first
+const SNAKE_BOARD: [u32; 16] = [
+    512, 256, 128, 64, //
+    4, 8, 16, 32, //
+    2, 0, 0, 0, //
+    0, 0, 0, 0,
+];
+
+#[test]
+fn empty_cells() {
+    let b = Board::from_board(SNAKE_BOARD);
+    assert_eq!(Heuristic::EmptyCells.eval(&b), 7.0);
+}
+
+#[test]
+fn monotonicity_only_penalizes_unsorted_lines() {
+    let mut board = [0; 16];
+    board[..4].copy_from_slice(&[2, 4, 8, 16]);
+    assert_eq!(Heuristic::Monotonicity.eval(&Board::from_board(board)), 0.0);
+
+    // 2 4 2 16: the dip from 4 to 2 costs one rank
+    board[..4].copy_from_slice(&[2, 4, 2, 16]);
+    assert_eq!(
+        Heuristic::Monotonicity.eval(&Board::from_board(board)),
+        -1.0
+    );
+}
+
+#[test]
+fn smoothness_compares_neighbouring_tiles() {
+    let mut board = [0; 16];
+    board[..4].copy_from_slice(&[2, 8, 0, 0]);
+    assert_eq!(Heuristic::Smoothness.eval(&Board::from_board(board)), -2.0);
+
+    board[..4].copy_from_slice(&[2, 0, 0, 2]);
+    assert_eq!(Heuristic::Smoothness.eval(&Board::from_board(board)), 0.0);
+}
+
+#[test]
+fn merge_potential_looks_past_empty_cells() {
+    let mut board = [0; 16];
+    board[..4].copy_from_slice(&[2, 0, 2, 4]);
+    board[4] = 4;
+    // 2 _ 2 can merge; the 4s are in different rows and columns
+    assert_eq!(
+        Heuristic::MergePotential.eval(&Board::from_board(board)),
+        1.0
+    );
+}
+
+#[test]
+fn max_in_corner() {
+    let b = Board::from_board(SNAKE_BOARD);
+    assert_eq!(Heuristic::MaxInCorner.eval(&b), 1.0);
+
+    let mut board = SNAKE_BOARD;
+    board.swap(0, 1);
+    assert_eq!(Heuristic::MaxInCorner.eval(&Board::from_board(board)), 0.0);
+}
+
+#[test]
+fn snake_prefers_big_tiles_at_the_head() {
+    let mut reversed = SNAKE_BOARD;
+    reversed.swap(0, 8);
+    assert!(
+        Heuristic::Snake.eval(&Board::from_board(SNAKE_BOARD))
+            > Heuristic::Snake.eval(&Board::from_board(reversed))
+    );
+}
+
+#[test]
+fn fibonacci_tiles_rank_by_their_place_in_the_sequence() {
+    let fibonacci = Rules {
+        tiles: Tiles::Fibonacci,
+        ..Rules::default()
+    };
+    let mut board = [0; 16];
+
+    // 3 and 5 are neighbours in the sequence, as 4 and 8 are in powers of two
+    board[..4].copy_from_slice(&[3, 5, 0, 0]);
+    let b = Board::from_board_with_rules(board, fibonacci);
+    assert_eq!(Heuristic::Smoothness.eval(&b), -1.0);
+    assert_eq!(Heuristic::MergePotential.eval(&b), 1.0);
+
+    board[..4].copy_from_slice(&[4, 8, 0, 0]);
+    let b = Board::from_board(board);
+    assert_eq!(Heuristic::Smoothness.eval(&b), -1.0);
+    assert_eq!(Heuristic::MergePotential.eval(&b), 0.0);
+}
+
+#[test]
+fn blocked_cells_are_neither_empty_nor_merge_partners() {
+    let blocked = Rules {
+        blocked: 1 << 1,
+        ..Rules::default()
+    };
+    let mut board = [0; 16];
+    board[0] = 2;
+    board[2] = 2;
+    let b = Board::from_board_with_rules(board, blocked);
+
+    assert_eq!(Heuristic::EmptyCells.eval(&b), 13.0);
+    assert_eq!(Heuristic::MergePotential.eval(&b), 0.0);
+}
+
+#[test]
+fn evaluator_sums_weighted_heuristics() {
+    let mut evaluator = Evaluator::new([0.0; 6]);
+    evaluator.set_weight(Heuristic::EmptyCells, 2.0);
+    evaluator.set_weight(Heuristic::MaxInCorner, 10.0);
+
+    let b = Board::from_board(SNAKE_BOARD);
+    assert_eq!(evaluator.eval(&b), 2.0 * 7.0 + 10.0);
+}
+
+#[test]
+fn evaluator_round_trips_through_text() {
+    let evaluator = Evaluator::new([2.5, -1.0, 0.125, 0.0, 3.0, 1e-3]);
+    assert_eq!(Evaluator::parse(&evaluator.to_string()).unwrap(), evaluator);
+}
+
+#[test]
+fn parse_skips_comments_and_defaults_to_zero() {
+    let evaluator = Evaluator::parse("# tuned\nempty 2 # per cell\n\ncorner 1.5\n").unwrap();
+    assert_eq!(evaluator.weight(Heuristic::EmptyCells), 2.0);
+    assert_eq!(evaluator.weight(Heuristic::MaxInCorner), 1.5);
+    assert_eq!(evaluator.weight(Heuristic::Snake), 0.0);
+}
+
+#[test]
+fn parse_reports_the_bad_line() {
+    let err = Evaluator::parse("empty 1\nsnek 2\n").unwrap_err();
+    assert!(err.starts_with("line 2:"), "{}", err);
+
+    let err = Evaluator::parse("empty one").unwrap_err();
+    assert!(err.contains("invalid weight"), "{}", err);
+
+    let err = Evaluator::parse("empty 1 2").unwrap_err();
+    assert!(err.contains("expected"), "{}", err);
+}
+
+#[test]
+fn tuning_is_reproducible_from_the_seed() {
+    let tuner = Tuner::new(Strategy::HillClimb { step: 0.5 }, 3, 2, 7).unwrap();
+    let start = Evaluator::default();
+
+    assert_eq!(tuner.tune(&start), tuner.tune(&start));
+    assert_eq!(tuner.fitness(&start, 1), tuner.fitness(&start, 1));
+}
+
+#[test]
+fn tuner_needs_games() {
+    let err = Tuner::new(Strategy::RandomSearch { max_weight: 1.0 }, 3, 0, 7).err();
+    assert!(err.is_some_and(|e| e.contains("games_per_eval")));
+}
+
+#[test]
+fn untuned_start_is_still_scored() {
+    let tuner = Tuner::new(Strategy::HillClimb { step: 0.5 }, 0, 2, 7).unwrap();
+    let start = Evaluator::default();
+    let (best, fitness) = tuner.tune(&start);
+
+    assert_eq!(best, start);
+    assert!(fitness > 0.0);
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use engine2048::{
+    engine::{Board, Move},
+    heuristics::Evaluator,
+    policy::Policy,
+    search::TimedSearch,
+};
+
+const MIDGAME: [u32; 16] = [
+    2, 4, 8, 16, //
+    0, 2, 4, 32, //
+    0, 0, 2, 64, //
+    0, 0, 0, 128,
+];
+
+#[test]
+fn stops_near_the_time_budget() {
+    let budget = Duration::from_millis(50);
+    let search = TimedSearch::new(Evaluator::default(), budget);
+    let result = search.search(&Board::from_board(MIDGAME));
+
+    assert!(result.best_move.is_some());
+    // the clock is checked every few hundred nodes, so allow some slack
+    assert!(
+        result.elapsed < budget + Duration::from_millis(100),
+        "took {:?}",
+        result.elapsed
+    );
+    assert!(result.depth < search.max_depth, "budget never ran out");
+}
+
+#[test]
+fn reports_depth_and_nodes() {
+    let mut search = TimedSearch::new(Evaluator::default(), Duration::from_secs(60));
+    let board = Board::from_board(MIDGAME);
+
+    search.max_depth = 1;
+    let shallow = search.search(&board);
+    assert_eq!(shallow.depth, 1);
+    assert!(shallow.nodes > 0);
+
+    search.max_depth = 2;
+    let deep = search.search(&board);
+    assert_eq!(deep.depth, 2);
+    assert!(deep.nodes > shallow.nodes);
+
+    let dir = deep.best_move.unwrap();
+    assert!(board.peek_move(dir).is_some());
+}
+
+#[test]
+fn a_tiny_budget_still_finishes_depth_one() {
+    let mut search = TimedSearch::new(Evaluator::default(), Duration::ZERO);
+    let board = Board::from_board(MIDGAME);
+
+    let dir = search.choose_move(&board);
+    let result = search.last_result().unwrap();
+    assert_eq!(result.depth, 1);
+    assert_eq!(dir, result.best_move);
+    assert!(dir.is_some());
+}
+
+#[test]
+fn finished_games_have_no_move() {
+    let stuck = Board::from_board([2, 4, 2, 16, 16, 2, 8, 32, 4, 64, 32, 16, 2, 8, 2, 4]);
+    let result = TimedSearch::new(Evaluator::default(), Duration::from_millis(10)).search(&stuck);
+
+    assert_eq!(result.best_move, None::<Move>);
+    assert_eq!(result.depth, 1);
+}
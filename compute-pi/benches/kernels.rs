@@ -0,0 +1,75 @@
+use compute_pi::{
+    methods::{self, Generator},
+    progress::Progress,
+    sampler::Sampler,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SAMPLES: u64 = 1 << 22;
+
+// 1, 2, 4, ... threads up to the number of cores, and the core count itself
+fn thread_counts() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts: Vec<usize> = (0..).map(|i| 1 << i).take_while(|&n| n < cores).collect();
+    counts.push(cores);
+    counts
+}
+
+// the single-threaded methods at a fixed sample count
+fn serial(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serial");
+    group.throughput(Throughput::Elements(SAMPLES));
+    group.sample_size(10);
+
+    let quiet = Progress::new(SAMPLES, false);
+    group.bench_function("compute_pi_simd", |bench| {
+        bench.iter(|| black_box(methods::compute_pi_simd(SAMPLES, 1, &quiet)))
+    });
+    group.bench_function("compute_pi", |bench| {
+        bench.iter(|| black_box(methods::compute_pi(SAMPLES, 1, &quiet)))
+    });
+    group.bench_function("compute_pi_b", |bench| {
+        bench.iter(|| black_box(methods::compute_pi_b(SAMPLES, 1, &quiet)))
+    });
+
+    group.finish();
+}
+
+// the rayon method on pools of each size, with both generators
+fn rayon(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compute_pi_rayon");
+    group.throughput(Throughput::Elements(SAMPLES));
+    group.sample_size(10);
+
+    let quiet = Progress::new(SAMPLES, false);
+    for threads in thread_counts() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+
+        for (name, generator) in [
+            ("chacha", Generator::Chacha),
+            ("xoshiro", Generator::Xoshiro),
+        ] {
+            group.bench_with_input(BenchmarkId::new(name, threads), &threads, |bench, _| {
+                pool.install(|| {
+                    bench.iter(|| {
+                        black_box(methods::compute_pi_rayon(
+                            Sampler::Uniform,
+                            generator,
+                            SAMPLES,
+                            1,
+                            &quiet,
+                        ))
+                    })
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, serial, rayon);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+use compute_pi::{kernel::Backend, lanes::Xoshiro128PlusX8, sampler::UnitRng};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const POINTS: usize = 1024;
+
+// x and y coordinates for POINTS points, without testing them
+fn bench_fill(c: &mut Criterion, name: &str, rng: &mut impl UnitRng) {
+    let mut group = c.benchmark_group("generate");
+    group.throughput(Throughput::Elements(POINTS as u64));
+
+    let mut xs = [0.0; POINTS];
+    let mut ys = [0.0; POINTS];
+    group.bench_function(name, |bench| {
+        bench.iter(|| {
+            rng.fill_unit(&mut xs);
+            rng.fill_unit(&mut ys);
+            black_box((&xs, &ys));
+        })
+    });
+
+    group.finish();
+}
+
+// generation plus the counting kernel, as the rayon method runs them
+fn bench_sample(c: &mut Criterion, name: &str, rng: &mut impl UnitRng) {
+    let mut group = c.benchmark_group("sample");
+    group.throughput(Throughput::Elements(POINTS as u64));
+
+    let backend = Backend::detect();
+    let mut xs = [0.0; POINTS];
+    let mut ys = [0.0; POINTS];
+    group.bench_function(name, |bench| {
+        bench.iter(|| {
+            rng.fill_unit(&mut xs);
+            rng.fill_unit(&mut ys);
+            black_box(backend.count_inside(&xs, &ys))
+        })
+    });
+
+    group.finish();
+}
+
+fn generators(c: &mut Criterion) {
+    let mut chacha = ChaCha8Rng::seed_from_u64(1);
+    let mut lanes = Xoshiro128PlusX8::from_rng(&mut ChaCha8Rng::seed_from_u64(1));
+
+    bench_fill(c, "chacha8", &mut chacha);
+    bench_fill(c, "xoshiro128+x8", &mut lanes);
+    bench_sample(c, "chacha8", &mut chacha);
+    bench_sample(c, "xoshiro128+x8", &mut lanes);
+}
+
+criterion_group!(benches, generators);
+criterion_main!(benches);
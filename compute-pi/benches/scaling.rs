@@ -0,0 +1,85 @@
+// Parallel efficiency of the rayon method: times a fixed-size run on 1, 2, ...
+// N threads and prints each count's speedup over one thread and the fraction
+// of that speedup which is ideal. N is the number of cores, or
+// SCALING_THREADS; the sample count is SCALING_SAMPLES (2^28 by default).
+//
+// Without --bench (as under `cargo test --benches`) it only checks that a
+// tiny run works.
+
+use std::time::{Duration, Instant};
+
+use compute_pi::{
+    methods::{self, Generator, CHUNK},
+    progress::Progress,
+    sampler::Sampler,
+};
+
+// runs per thread count; the fastest one is reported
+const REPEATS: usize = 3;
+
+fn env_or(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+fn time_run(threads: usize, generator: Generator, samples: u64) -> Duration {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    let quiet = Progress::new(samples, false);
+
+    (0..REPEATS)
+        .map(|_| {
+            let start = Instant::now();
+            pool.install(|| {
+                methods::compute_pi_rayon(Sampler::Uniform, generator, samples, 1, &quiet)
+            });
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let bench = std::env::args().any(|arg| arg == "--bench");
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let max_threads = env_or("SCALING_THREADS", cores as u64) as usize;
+    let samples = if bench {
+        env_or("SCALING_SAMPLES", 1 << 28)
+    } else {
+        CHUNK
+    };
+
+    for (name, generator) in [
+        ("chacha", Generator::Chacha),
+        ("xoshiro", Generator::Xoshiro),
+    ] {
+        println!("{} generator, {} samples", name, samples);
+        println!(
+            "{:>8}{:>12}{:>14}{:>10}{:>12}",
+            "threads", "time", "M samples/s", "speedup", "efficiency"
+        );
+
+        let base = time_run(1, generator, samples);
+        for threads in 1..=max_threads {
+            let time = if threads == 1 {
+                base
+            } else {
+                time_run(threads, generator, samples)
+            };
+            let speedup = base.as_secs_f64() / time.as_secs_f64();
+            println!(
+                "{:>8}{:>12.2?}{:>14.1}{:>10.2}{:>11.0}%",
+                threads,
+                time,
+                samples as f64 / time.as_secs_f64() / 1e6,
+                speedup,
+                speedup / threads as f64 * 100.0
+            );
+        }
+        println!();
+    }
+}
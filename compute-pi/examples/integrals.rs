@@ -0,0 +1,57 @@
+// Monte Carlo estimates of pi, unit ball volumes and a few integrals with known
+// values, next to the exact answers
+
+use compute_pi::integrate::{self, Domain, Integral};
+use std::f64::consts::PI;
+
+const SAMPLES: u64 = 10_000_000;
+const SEED: u64 = 1;
+
+fn report(name: &str, integral: Integral, exact: f64) {
+    println!(
+        "{:<28} {:>12.6} +- {:<10.2e} exact {:>12.6} ({:+.1} std errors)",
+        name,
+        integral.value,
+        integral.half_width(0.99),
+        exact,
+        (integral.value - exact) / integral.std_error
+    );
+}
+
+fn main() {
+    report("pi (unit disc)", integrate::pi(SAMPLES, SEED), PI);
+
+    for dims in 3..=10 {
+        report(
+            &format!("volume of unit {}-ball", dims),
+            integrate::ball_volume(dims, SAMPLES, SEED),
+            integrate::exact_ball_volume(dims),
+        );
+    }
+
+    let unit = Domain::cube(1, 0.0, 1.0);
+    report(
+        "x^2 on [0, 1]",
+        integrate::integrate(|x| x[0] * x[0], &unit, SAMPLES, SEED),
+        1.0 / 3.0,
+    );
+
+    let half_period = Domain::new(&[(0.0, PI)]);
+    report(
+        "sin x on [0, pi]",
+        integrate::integrate(|x| x[0].sin(), &half_period, SAMPLES, SEED),
+        2.0,
+    );
+
+    let plane = Domain::cube(2, -6.0, 6.0);
+    report(
+        "exp(-x^2 - y^2) on R^2",
+        integrate::integrate(
+            |x| (-(x[0] * x[0] + x[1] * x[1])).exp(),
+            &plane,
+            SAMPLES,
+            SEED,
+        ),
+        PI,
+    );
+}
@@ -0,0 +1,162 @@
+// Hexadecimal digits of pi from anywhere in the expansion with the
+// Bailey-Borwein-Plouffe formula
+//   pi = sum_k 16^-k (4/(8k+1) - 2/(8k+4) - 1/(8k+5) - 1/(8k+6))
+// Multiplying by 16^n and keeping only fractional parts gives the digits after
+// position n using modular exponentiation, without the digits before it.
+// Positions count from 1, the first hex digit after the point.
+
+use rayon::prelude::*;
+
+// first 1000 hex digits, for checking computed digits
+pub const KNOWN_HEX_DIGITS: &str = include_str!("../data/pi-hex-1000.txt");
+
+// last position the digits can be computed at: the moduli 8k + j stay below
+// 2^32 up to here, so their products fit in a u64
+pub const MAX_POSITION: u64 = 500_000_000;
+
+// digits taken from each evaluation of the series. An f64 fraction holds about
+// 13 hex digits; the rest absorb rounding error accumulated over the n terms.
+// Each term rounds twice by at most 2^-53, and the four series are weighted
+// 4 + 2 + 1 + 1 = 8, so the error is about 8 * sqrt(2n) * 2^-53 when the
+// roundings are independent: 2^-35 at MAX_POSITION, 11 bits below the last of
+// the 24 bits kept. A digit is still wrong if the digits after it are a run
+// of about three 0s or Fs, as with any fixed precision
+const DIGITS_PER_EVAL: usize = 6;
+
+// 16^exp mod modulus, for moduli below 2^32
+fn pow16_mod(mut exp: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut base = 16 % modulus;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+
+    result
+}
+
+// fractional part of 16^n * sum_k 1 / (16^k (8k + j))
+fn series(n: u64, j: u64) -> f64 {
+    let mut sum = 0.0;
+
+    // terms with k <= n, whose integer parts are dropped by working mod 8k+j
+    for k in 0..=n {
+        let denom = 8 * k + j;
+        sum += pow16_mod(n - k, denom) as f64 / denom as f64;
+        sum = sum.fract();
+    }
+
+    // the tail, where 16^(n-k) < 1
+    let mut k = n + 1;
+    loop {
+        let term = 16_f64.powi(-((k - n) as i32)) / (8 * k + j) as f64;
+        if term < 1e-17 {
+            break;
+        }
+        sum += term;
+        k += 1;
+    }
+
+    sum.fract()
+}
+
+// hex digits of pi starting at `position`, from one evaluation of the series
+fn digits_from(position: u64, count: usize) -> Vec<u8> {
+    assert!((1..=MAX_POSITION).contains(&position) && count <= DIGITS_PER_EVAL);
+
+    let n = position - 1;
+    let x = 4.0 * series(n, 1) - 2.0 * series(n, 4) - series(n, 5) - series(n, 6);
+    let mut frac = x.rem_euclid(1.0);
+
+    (0..count)
+        .map(|_| {
+            frac *= 16.0;
+            let digit = frac as u8;
+            frac -= digit as f64;
+            digit
+        })
+        .collect()
+}
+
+// the hex digit of pi at `position` (1 is the first digit after the point)
+pub fn hex_digit(position: u64) -> u8 {
+    digits_from(position, 1)[0]
+}
+
+// whether `count` digits from `position` all lie between 1 and MAX_POSITION
+pub fn in_range(position: u64, count: usize) -> bool {
+    position >= 1
+        && (position - 1)
+            .checked_add(count as u64)
+            .is_some_and(|last| last <= MAX_POSITION)
+}
+
+// `count` hex digits starting at `position`, as an uppercase string. Blocks of
+// digits are independent, so they are computed in parallel. The digits must
+// be `in_range`
+pub fn hex_digits(position: u64, count: usize) -> String {
+    assert!(in_range(position, count));
+
+    let blocks: Vec<Vec<u8>> = (0..count.div_ceil(DIGITS_PER_EVAL))
+        .into_par_iter()
+        .map(|block| {
+            let start = block * DIGITS_PER_EVAL;
+            let len = DIGITS_PER_EVAL.min(count - start);
+            digits_from(position + start as u64, len)
+        })
+        .collect();
+
+    blocks
+        .concat()
+        .iter()
+        .map(|d| {
+            char::from_digit(*d as u32, 16)
+                .unwrap()
+                .to_ascii_uppercase()
+        })
+        .collect()
+}
+
+// the known hex digits starting at `position`, as far as the bundled table goes
+pub fn known_hex_digits(position: u64) -> &'static str {
+    let known = &KNOWN_HEX_DIGITS.trim()[2..];
+    known.get(position as usize - 1..).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_of_positions() {
+        assert!(in_range(1, 16));
+        assert!(in_range(MAX_POSITION, 1));
+        assert!(in_range(MAX_POSITION - 5, 6));
+        assert!(!in_range(MAX_POSITION - 5, 7));
+        assert!(!in_range(0, 1));
+        assert!(!in_range(2, usize::MAX));
+    }
+
+    #[test]
+    fn first_digits() {
+        assert_eq!(hex_digits(1, 16), "243F6A8885A308D3");
+        assert_eq!(hex_digit(1), 2);
+        assert_eq!(hex_digit(3), 3);
+    }
+
+    #[test]
+    fn matches_known_table() {
+        assert_eq!(hex_digits(1, 1000), known_hex_digits(1));
+        assert_eq!(hex_digits(990, 11), known_hex_digits(990));
+    }
+
+    #[test]
+    fn digits_at_one_million() {
+        // Bailey, Borwein and Plouffe's table of digits from position 10^6
+        assert_eq!(hex_digits(1_000_000, 14), "26C65E52CB4593");
+    }
+}
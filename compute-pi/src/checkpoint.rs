@@ -0,0 +1,101 @@
+// Progress of a seeded run, saved so it can be resumed. Chunk i of a run
+// always draws from RNG stream i of the seed (see `stream_rng`), so the seed
+// and the number of finished chunks pin down the generator state of every
+// worker: resuming starts the remaining chunks on their own streams and ends
+// with exactly the counts of an uninterrupted run.
+//
+// The file is one "key value" pair per line, with # comments.
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub seed: u64,
+    pub samples: u64,
+    // how the points are drawn, e.g. "uniform chacha"; a run only resumes a
+    // checkpoint with the same settings
+    pub settings: String,
+    // chunks [0, chunks_done) are counted in `inside`
+    pub chunks_done: u64,
+    pub inside: u64,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+impl Checkpoint {
+    pub fn new(seed: u64, samples: u64, settings: String) -> Self {
+        Self {
+            seed,
+            samples,
+            settings,
+            chunks_done: 0,
+            inside: 0,
+        }
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut checkpoint = Checkpoint::new(0, 0, String::new());
+        let mut seen = 0;
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(' ')
+                .ok_or_else(|| invalid_data(format!("expected \"key value\", got {:?}", line)))?;
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| invalid_data(format!("{}: not a number: {:?}", key, value)))
+            };
+
+            match key {
+                "seed" => checkpoint.seed = number()?,
+                "samples" => checkpoint.samples = number()?,
+                "settings" => checkpoint.settings = value.to_string(),
+                "chunks_done" => checkpoint.chunks_done = number()?,
+                "inside" => checkpoint.inside = number()?,
+                _ => return Err(invalid_data(format!("unknown key {:?}", key))),
+            }
+            seen += 1;
+        }
+
+        if seen != 5 {
+            return Err(invalid_data("incomplete checkpoint".to_string()));
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    // writes to a temporary file first, so an interrupted save leaves the
+    // previous checkpoint intact
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, self.to_string())?;
+        fs::rename(tmp, path)
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# compute-pi checkpoint")?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "samples {}", self.samples)?;
+        writeln!(f, "settings {}", self.settings)?;
+        writeln!(f, "chunks_done {}", self.chunks_done)?;
+        writeln!(f, "inside {}", self.inside)
+    }
+}
@@ -0,0 +1,139 @@
+// Exact decimal digits of pi in fixed-point big integer arithmetic: pi is
+// computed as floor(pi * 10^(digits + GUARD_DIGITS)) and the guard digits are
+// dropped, which absorbs the rounding error of the truncating divisions.
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Signed, Zero};
+
+// first 10000 decimals, for checking computed digits
+pub const KNOWN_DIGITS: &str = include_str!("../data/pi-10000.txt");
+
+const GUARD_DIGITS: usize = 10;
+
+// below this many terms, binary splitting recurses on the current thread
+const SPLIT_SEQUENTIAL_TERMS: u64 = 256;
+
+// 640320^3 / 24
+const CHUDNOVSKY_C3_OVER_24: u64 = 10939058860032000;
+
+fn pow10(exp: usize) -> BigUint {
+    BigUint::from(10_u32).pow(exp as u32)
+}
+
+// formats floor(pi * 10^digits) as "3.1415..."
+fn format_digits(scaled: &BigUint, digits: usize) -> String {
+    let s = scaled.to_str_radix(10);
+    format!("{}.{}", &s[..1], &s[1..=digits])
+}
+
+// P, Q and T of the Chudnovsky series over terms [a, b)
+fn binary_split(a: u64, b: u64) -> (BigInt, BigInt, BigInt) {
+    if b - a == 1 {
+        if a == 0 {
+            return (BigInt::one(), BigInt::one(), BigInt::from(13591409));
+        }
+
+        let p = -BigInt::from(6 * a - 5) * (2 * a - 1) * (6 * a - 1);
+        let q = BigInt::from(a) * a * a * CHUDNOVSKY_C3_OVER_24;
+        let t = &p * (13591409 + 545140134 * a);
+        return (p, q, t);
+    }
+
+    let m = (a + b) / 2;
+    let ((pam, qam, tam), (pmb, qmb, tmb)) = if b - a > SPLIT_SEQUENTIAL_TERMS {
+        rayon::join(|| binary_split(a, m), || binary_split(m, b))
+    } else {
+        (binary_split(a, m), binary_split(m, b))
+    };
+
+    let t = tam * &qmb + &pam * tmb;
+    (pam * pmb, qam * qmb, t)
+}
+
+// Chudnovsky's series with binary splitting. Each term adds about 14.18
+// digits; the two halves of every split are evaluated in parallel
+pub fn chudnovsky(digits: usize) -> String {
+    let terms = (digits as f64 / 14.181647462725477) as u64 + 2;
+    let (_, q, t) = binary_split(0, terms);
+
+    // pi = 426880 * sqrt(10005) * Q / T
+    let one = pow10(digits + GUARD_DIGITS);
+    let sqrt_10005 = (&one * &one * 10005_u32).sqrt();
+    let numerator = sqrt_10005 * 426880_u32 * q.magnitude();
+    assert!(t.is_positive());
+    let scaled = numerator / t.magnitude() / pow10(GUARD_DIGITS);
+
+    format_digits(&scaled, digits)
+}
+
+// arctan(1 / x) * `one` from its Taylor series
+fn arctan_inv(x: u32, one: &BigUint) -> BigUint {
+    let x2 = BigUint::from(x) * x;
+    let mut term = one / x;
+    let mut positive = term.clone();
+    let mut negative = BigUint::zero();
+
+    let mut n = 1_u32;
+    while !term.is_zero() {
+        term /= &x2;
+        n += 2;
+        if n % 4 == 1 {
+            positive += &term / n;
+        } else {
+            negative += &term / n;
+        }
+    }
+
+    positive - negative
+}
+
+// Machin's formula pi = 16 arctan(1/5) - 4 arctan(1/239), with the two series
+// summed in parallel. Converges linearly, so it is only here for comparison
+pub fn machin(digits: usize) -> String {
+    let one = pow10(digits + GUARD_DIGITS);
+    let (a, b) = rayon::join(|| arctan_inv(5, &one), || arctan_inv(239, &one));
+    let scaled = (a * 16_u32 - b * 4_u32) / pow10(GUARD_DIGITS);
+
+    format_digits(&scaled, digits)
+}
+
+// number of leading decimals of `computed` that agree with `KNOWN_DIGITS`
+pub fn matching_digits(computed: &str) -> usize {
+    let known = KNOWN_DIGITS.trim();
+    computed
+        .bytes()
+        .zip(known.bytes())
+        .skip(2)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(digits: usize) -> &'static str {
+        &KNOWN_DIGITS.trim()[..digits + 2]
+    }
+
+    #[test]
+    fn chudnovsky_matches_known_digits() {
+        for digits in [1, 2, 3, 14, 15, 100, 1000, 10000] {
+            assert_eq!(chudnovsky(digits), known(digits), "{} digits", digits);
+        }
+    }
+
+    #[test]
+    fn machin_matches_known_digits() {
+        for digits in [1, 2, 3, 14, 15, 100, 1000] {
+            assert_eq!(machin(digits), known(digits), "{} digits", digits);
+        }
+    }
+
+    #[test]
+    fn counts_matching_digits() {
+        assert_eq!(matching_digits("3.14"), 2);
+        assert_eq!(matching_digits("3.1416"), 3);
+        assert_eq!(matching_digits(known(10000)), 10000);
+    }
+}
@@ -0,0 +1,402 @@
+// Spreading a seeded run over several processes. A coordinator listens on a
+// TCP port and hands out ranges of chunks; workers connect, count the hits in
+// each range they are given and send the count back. Since chunk i always
+// draws from RNG stream i of the seed, the total does not depend on which
+// worker counted which range, and a range whose worker disconnects, or takes
+// longer than the task timeout to answer, is simply handed to the next worker
+// that asks.
+//
+// The protocol is one line of text per message:
+//   coordinator -> worker: TASK <seed> <samples> <first chunk> <end chunk> <settings>
+//                          DONE
+//   worker -> coordinator: RESULT <first chunk> <end chunk> <inside>
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    ops::Range,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+// how often the coordinator checks for new workers while it waits
+const ACCEPT_POLL: Duration = Duration::from_millis(10);
+
+// how long a worker gets to answer a task unless the coordinator is told
+// otherwise
+pub const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub seed: u64,
+    pub samples: u64,
+    pub chunks: Range<u64>,
+    // how to draw the points, as in `Checkpoint::settings`
+    pub settings: String,
+}
+
+impl Task {
+    fn to_line(&self) -> String {
+        format!(
+            "TASK {} {} {} {} {}\n",
+            self.seed, self.samples, self.chunks.start, self.chunks.end, self.settings
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.trim().splitn(6, ' ');
+        if parts.next()? != "TASK" {
+            return None;
+        }
+
+        let mut number = || parts.next()?.parse::<u64>().ok();
+        let (seed, samples, start, end) = (number()?, number()?, number()?, number()?);
+        Some(Task {
+            seed,
+            samples,
+            chunks: start..end,
+            settings: parts.next()?.to_string(),
+        })
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+pub struct Coordinator {
+    seed: u64,
+    samples: u64,
+    settings: String,
+    // total chunks in the run
+    chunks: u64,
+    // chunks handed out per task, at least one
+    chunks_per_task: u64,
+    // how long a worker gets to answer before its task goes back on the queue
+    task_timeout: Duration,
+}
+
+// ranges still to hand out and hits counted so far, shared by the
+// connection threads
+struct Progress {
+    pending: VecDeque<Range<u64>>,
+    chunks_left: u64,
+    inside: u64,
+}
+
+type Shared = Arc<(Mutex<Progress>, Condvar)>;
+
+impl Coordinator {
+    pub fn new(
+        seed: u64,
+        samples: u64,
+        settings: String,
+        chunks: u64,
+        chunks_per_task: u64,
+    ) -> Self {
+        assert!(chunks_per_task > 0, "tasks must have at least one chunk");
+
+        Self {
+            seed,
+            samples,
+            settings,
+            chunks,
+            chunks_per_task,
+            task_timeout: DEFAULT_TASK_TIMEOUT,
+        }
+    }
+
+    pub fn task_timeout(mut self, timeout: Duration) -> Self {
+        self.task_timeout = timeout;
+        self
+    }
+
+    // serves tasks to every worker that connects to `listener` until all
+    // chunks are counted, and returns the total number of hits once every
+    // connected worker has been told it is done
+    pub fn run(&self, listener: TcpListener) -> io::Result<u64> {
+        let pending = (0..self.chunks)
+            .step_by(self.chunks_per_task as usize)
+            .map(|start| start..self.chunks.min(start + self.chunks_per_task))
+            .collect();
+        let shared: Shared = Arc::new((
+            Mutex::new(Progress {
+                pending,
+                chunks_left: self.chunks,
+                inside: 0,
+            }),
+            Condvar::new(),
+        ));
+
+        let mut servers = Vec::new();
+        listener.set_nonblocking(true)?;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    stream.set_read_timeout(Some(self.task_timeout))?;
+                    let shared = shared.clone();
+                    let (seed, samples, settings) =
+                        (self.seed, self.samples, self.settings.clone());
+                    servers.push(thread::spawn(move || {
+                        serve(stream, shared, seed, samples, settings)
+                    }));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    let (lock, cvar) = &*shared;
+                    let progress = cvar
+                        .wait_timeout_while(lock.lock().unwrap(), ACCEPT_POLL, |p| {
+                            p.chunks_left > 0
+                        })
+                        .unwrap()
+                        .0;
+                    if progress.chunks_left == 0 {
+                        let inside = progress.inside;
+                        drop(progress);
+                        for server in servers {
+                            let _ = server.join();
+                        }
+                        return Ok(inside);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+// hands tasks to one worker until there are none left or it goes away. A
+// task the worker doesn't return in time goes back on the queue, and the
+// worker is dropped
+fn serve(stream: TcpStream, shared: Shared, seed: u64, samples: u64, settings: String) {
+    let (lock, cvar) = &*shared;
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(read_half);
+    let mut writer = stream;
+
+    loop {
+        // wait for a range, or for the run to finish; ranges can reappear
+        // while others are out if their workers disconnect
+        let chunks = {
+            let mut progress = cvar
+                .wait_while(lock.lock().unwrap(), |p| {
+                    p.pending.is_empty() && p.chunks_left > 0
+                })
+                .unwrap();
+            match progress.pending.pop_front() {
+                Some(chunks) => chunks,
+                None => {
+                    let _ = writer.write_all(b"DONE\n");
+                    return;
+                }
+            }
+        };
+
+        let task = Task {
+            seed,
+            samples,
+            chunks: chunks.clone(),
+            settings: settings.clone(),
+        };
+        match exchange(&mut reader, &mut writer, &task) {
+            Ok(inside) => {
+                let mut progress = lock.lock().unwrap();
+                progress.inside += inside;
+                progress.chunks_left -= chunks.end - chunks.start;
+                cvar.notify_all();
+            }
+            Err(_) => {
+                lock.lock().unwrap().pending.push_front(chunks);
+                cvar.notify_all();
+                return;
+            }
+        }
+    }
+}
+
+// sends `task` and waits for its result
+fn exchange(reader: &mut impl BufRead, writer: &mut impl Write, task: &Task) -> io::Result<u64> {
+    writer.write_all(task.to_line().as_bytes())?;
+
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts[..] {
+        ["RESULT", start, end, inside]
+            if start.parse() == Ok(task.chunks.start) && end.parse() == Ok(task.chunks.end) =>
+        {
+            inside
+                .parse()
+                .map_err(|_| invalid_data(format!("bad count in {:?}", line)))
+        }
+        _ => Err(invalid_data(format!("unexpected reply {:?}", line))),
+    }
+}
+
+// connects to the coordinator at `addr` and counts every task it is given
+// with `count` until told it is done; returns the number of tasks counted
+pub fn run_worker(addr: impl ToSocketAddrs, count: impl Fn(&Task) -> u64) -> io::Result<u64> {
+    let stream = TcpStream::connect(addr)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut tasks = 0;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        if line.trim() == "DONE" {
+            return Ok(tasks);
+        }
+
+        let task = Task::parse(&line)
+            .ok_or_else(|| invalid_data(format!("unexpected message {:?}", line)))?;
+        let inside = count(&task);
+        writer.write_all(
+            format!(
+                "RESULT {} {} {}\n",
+                task.chunks.start, task.chunks.end, inside
+            )
+            .as_bytes(),
+        )?;
+        tasks += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a stand-in for counting hits that depends only on the chunks
+    fn fake_count(task: &Task) -> u64 {
+        task.chunks.clone().map(|c| c * c + task.seed).sum()
+    }
+
+    fn start_coordinator(chunks: u64) -> (String, thread::JoinHandle<io::Result<u64>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let coordinator =
+            Coordinator::new(3, chunks * 100, "uniform chacha".to_string(), chunks, 4)
+                .task_timeout(Duration::from_millis(200));
+
+        (addr, thread::spawn(move || coordinator.run(listener)))
+    }
+
+    fn expected(chunks: u64) -> u64 {
+        (0..chunks).map(|c| c * c + 3).sum()
+    }
+
+    #[test]
+    fn task_round_trip() {
+        let task = Task {
+            seed: 1,
+            samples: 2,
+            chunks: 3..4,
+            settings: "halton xoshiro".to_string(),
+        };
+        assert_eq!(Task::parse(&task.to_line()), Some(task));
+    }
+
+    #[test]
+    fn workers_share_the_run() {
+        let (addr, coordinator) = start_coordinator(50);
+
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let addr = addr.clone();
+                // slow enough that every worker connects before the run ends
+                let slow_count = |task: &Task| {
+                    thread::sleep(Duration::from_millis(10));
+                    fake_count(task)
+                };
+                thread::spawn(move || run_worker(addr, slow_count).unwrap())
+            })
+            .collect();
+
+        assert_eq!(coordinator.join().unwrap().unwrap(), expected(50));
+        let tasks: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+        assert_eq!(tasks, 13);
+    }
+
+    #[test]
+    fn abandoned_task_is_reassigned() {
+        let (addr, coordinator) = start_coordinator(10);
+
+        // takes a task and disconnects without answering
+        let mut quitter = BufReader::new(TcpStream::connect(&addr).unwrap());
+        let mut line = String::new();
+        quitter.read_line(&mut line).unwrap();
+        assert!(line.starts_with("TASK 3 1000 0 4 "));
+        drop(quitter);
+
+        run_worker(&addr, fake_count).unwrap();
+        assert_eq!(coordinator.join().unwrap().unwrap(), expected(10));
+    }
+
+    #[test]
+    fn wrong_reply_is_reassigned() {
+        let (addr, coordinator) = start_coordinator(8);
+
+        let mut liar = TcpStream::connect(&addr).unwrap();
+        let mut reader = BufReader::new(liar.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        liar.write_all(b"RESULT 5 6 7\n").unwrap();
+
+        run_worker(&addr, fake_count).unwrap();
+        assert_eq!(coordinator.join().unwrap().unwrap(), expected(8));
+    }
+
+    #[test]
+    fn silent_task_is_reassigned() {
+        let (addr, coordinator) = start_coordinator(10);
+
+        // takes a task and never answers, but stays connected
+        let mut sleeper = BufReader::new(TcpStream::connect(&addr).unwrap());
+        let mut line = String::new();
+        sleeper.read_line(&mut line).unwrap();
+        assert!(line.starts_with("TASK 3 1000 0 4 "));
+
+        run_worker(&addr, fake_count).unwrap();
+        assert_eq!(coordinator.join().unwrap().unwrap(), expected(10));
+
+        // the coordinator hung up on it
+        line.clear();
+        assert_eq!(sleeper.read_line(&mut line).unwrap(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one chunk")]
+    fn tasks_need_chunks() {
+        Coordinator::new(3, 100, "uniform chacha".to_string(), 1, 0);
+    }
+
+    #[test]
+    fn workers_are_told_before_the_run_returns() {
+        let (addr, coordinator) = start_coordinator(4);
+
+        let mut worker = TcpStream::connect(&addr).unwrap();
+        let mut reader = BufReader::new(worker.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let task = Task::parse(&line).unwrap();
+        let reply = format!("RESULT 0 4 {}\n", fake_count(&task));
+        worker.write_all(reply.as_bytes()).unwrap();
+
+        assert_eq!(coordinator.join().unwrap().unwrap(), expected(4));
+
+        // already sent, so this doesn't wait on the coordinator
+        reader.get_ref().set_nonblocking(true).unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "DONE\n");
+    }
+}
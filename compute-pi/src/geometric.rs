@@ -0,0 +1,346 @@
+// Other ways of estimating pi from random experiments. Each trial of an
+// estimator yields a number whose mean is a known function of pi, e.g. the
+// probability 2/pi that Buffon's needle crosses a line. Trials are run by
+// `integrate`'s chunked runner, each chunk from its own ChaCha stream, so a
+// seed gives the same estimate for any number of threads.
+// None of the experiments use pi to draw their trials: random directions come
+// from points in the unit disc rather than from random angles.
+
+use clap::ValueEnum;
+use rand::{Rng, RngCore};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{integrate::run_trials, stats::z_score};
+
+pub trait Estimator: Sync {
+    // one trial of the experiment
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64;
+
+    // pi from the mean of the trials
+    fn pi(&self, mean: f64) -> f64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PiEstimate {
+    pub pi: f64,
+    pub std_error: f64,
+    pub samples: u64,
+}
+
+impl PiEstimate {
+    // half the width of the two-sided confidence interval at `confidence`
+    pub fn half_width(&self, confidence: f64) -> f64 {
+        z_score(confidence) * self.std_error
+    }
+}
+
+// runs `samples` trials of `estimator` in parallel, at least two so there is a
+// standard error. That is the trials' standard error carried through
+// `Estimator::pi` (delta method)
+pub fn estimate<E: Estimator + ?Sized>(estimator: &E, samples: u64, seed: u64) -> PiEstimate {
+    assert!(samples > 1);
+
+    let moments = run_trials(samples, seed, || (), |_, rng| estimator.trial(rng));
+    let mean_error = (moments.m2 / (moments.n - 1) as f64 / moments.n as f64).sqrt();
+    let h = 1e-6 * moments.mean.abs();
+    let slope = (estimator.pi(moments.mean + h) - estimator.pi(moments.mean - h)) / (2.0 * h);
+
+    PiEstimate {
+        pi: estimator.pi(moments.mean),
+        std_error: slope.abs() * mean_error,
+        samples: moments.n,
+    }
+}
+
+// a uniformly random direction as (cos, sin), from a point in the unit disc
+fn direction(rng: &mut impl Rng) -> (f64, f64) {
+    loop {
+        let (x, y): (f64, f64) = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        let r2 = x * x + y * y;
+        if r2 > 0.0 && r2 <= 1.0 {
+            let r = r2.sqrt();
+            return (x / r, y / r);
+        }
+    }
+}
+
+fn hit(inside: bool) -> f64 {
+    if inside {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+// the usual experiment: a point in the unit square lands in the quarter disc
+// with probability pi/4
+pub struct Disc;
+
+impl Estimator for Disc {
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64 {
+        let (x, y): (f64, f64) = (rng.gen(), rng.gen());
+        hit(x * x + y * y <= 1.0)
+    }
+
+    fn pi(&self, mean: f64) -> f64 {
+        4.0 * mean
+    }
+}
+
+// a needle of length 1 dropped on lines 1 apart crosses one with
+// probability 2/pi
+pub struct BuffonNeedle;
+
+impl Estimator for BuffonNeedle {
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64 {
+        // distance from the needle's centre to the nearest line, and the
+        // needle's angle to the lines
+        let distance = rng.gen::<f64>() * 0.5;
+        let (_, sin) = direction(rng);
+        hit(distance <= 0.5 * sin.abs())
+    }
+
+    fn pi(&self, mean: f64) -> f64 {
+        2.0 / mean
+    }
+}
+
+// Bertrand's random chord through two uniform points on the unit circle has
+// mean length 4/pi
+pub struct RandomChord;
+
+impl Estimator for RandomChord {
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64 {
+        let (a, b) = (direction(rng), direction(rng));
+        (a.0 - b.0).hypot(a.1 - b.1)
+    }
+
+    fn pi(&self, mean: f64) -> f64 {
+        4.0 / mean
+    }
+}
+
+// two random integers are coprime with probability 6/pi^2. Drawing them below
+// 2^32 instead of from all integers biases that by about 1e-9
+pub struct CoprimePairs;
+
+impl Estimator for CoprimePairs {
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64 {
+        let a = rng.gen_range(1..=u32::MAX);
+        let b = rng.gen_range(1..=u32::MAX);
+        hit(gcd(a, b) == 1)
+    }
+
+    fn pi(&self, mean: f64) -> f64 {
+        (6.0 / mean).sqrt()
+    }
+}
+
+// binary gcd
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    if a == 0 || b == 0 {
+        return a | b;
+    }
+
+    let shift = (a | b).trailing_zeros();
+    a >>= a.trailing_zeros();
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= a;
+        if b == 0 {
+            return a << shift;
+        }
+    }
+}
+
+// the distance a simple random walk of n steps ends from its start has mean
+//   E|S_n| = sqrt(2n/pi) (1 - 1/(4n) + 1/(32n^2) + O(n^-3))
+// for even n, so each trial is |S_n| divided by everything but the
+// 1/sqrt(pi). Odd n don't follow it, as E|S_{2m-1}| = E|S_{2m}|
+pub struct RandomWalk {
+    steps: u32,
+}
+
+impl RandomWalk {
+    // below this the dropped O(n^-3) term biases pi by more than 3e-7
+    pub const MIN_STEPS: u32 = 64;
+
+    // `steps` must be even and at least MIN_STEPS
+    pub const fn new(steps: u32) -> Self {
+        assert!(
+            steps >= Self::MIN_STEPS && steps.is_multiple_of(2),
+            "random walks need an even number of steps, at least 64"
+        );
+        Self { steps }
+    }
+}
+
+impl Estimator for RandomWalk {
+    fn trial(&self, rng: &mut ChaCha8Rng) -> f64 {
+        // steps are drawn 64 at a time from the bits of a u64, and the rest
+        // from the low bits of one more
+        let mut rights: u32 = (0..self.steps / 64)
+            .map(|_| rng.next_u64().count_ones())
+            .sum();
+        let rest = self.steps % 64;
+        if rest > 0 {
+            rights += (rng.next_u64() & ((1 << rest) - 1)).count_ones();
+        }
+        let distance = (2 * rights as i64 - self.steps as i64).abs() as f64;
+
+        let n = self.steps as f64;
+        distance / ((2.0 * n).sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (32.0 * n * n)))
+    }
+
+    fn pi(&self, mean: f64) -> f64 {
+        1.0 / (mean * mean)
+    }
+}
+
+const WALK: RandomWalk = RandomWalk::new(1024);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Experiment {
+    /// points in the unit square falling in the quarter disc
+    Disc,
+    /// Buffon's needle crossing parallel lines
+    Buffon,
+    /// length of a chord between random points on a circle
+    Chord,
+    /// pairs of random integers being coprime
+    Coprime,
+    /// distance covered by a 1024-step random walk
+    Walk,
+}
+
+impl Experiment {
+    pub const ALL: [Experiment; 5] = [
+        Experiment::Disc,
+        Experiment::Buffon,
+        Experiment::Chord,
+        Experiment::Coprime,
+        Experiment::Walk,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Experiment::Disc => "disc",
+            Experiment::Buffon => "buffon",
+            Experiment::Chord => "chord",
+            Experiment::Coprime => "coprime",
+            Experiment::Walk => "walk",
+        }
+    }
+
+    pub fn estimator(&self) -> &'static dyn Estimator {
+        match self {
+            Experiment::Disc => &Disc,
+            Experiment::Buffon => &BuffonNeedle,
+            Experiment::Chord => &RandomChord,
+            Experiment::Coprime => &CoprimePairs,
+            Experiment::Walk => &WALK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{integrate::CHUNK, sampler::stream_rng};
+    use std::f64::consts::PI;
+
+    const SAMPLES: u64 = 200_000;
+
+    #[test]
+    fn every_experiment_estimates_pi() {
+        for experiment in Experiment::ALL {
+            let estimate = estimate(experiment.estimator(), SAMPLES, 1);
+            let error = (estimate.pi - PI).abs();
+
+            assert_eq!(estimate.samples, SAMPLES);
+            assert!(
+                error <= 5.0 * estimate.std_error,
+                "{}: {:?} is {} away from pi",
+                experiment.name(),
+                estimate,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn disc_error_matches_binomial() {
+        let estimate = estimate(&Disc, SAMPLES, 2);
+        let p = estimate.pi / 4.0;
+        let binomial = 4.0 * (p * (1.0 - p) / SAMPLES as f64).sqrt();
+
+        assert!((estimate.std_error / binomial - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn directions_are_unit_vectors() {
+        let mut rng = stream_rng(3, 0);
+        for _ in 0..1000 {
+            let (cos, sin) = direction(&mut rng);
+            assert!((cos.hypot(sin) - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn gcds() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(17, 5), 1);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(1 << 20, 3 << 12), 1 << 12);
+    }
+
+    #[test]
+    fn seeded_estimate_does_not_depend_on_threads() {
+        let run = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| estimate(&RandomChord, 3 * CHUNK + 7, 5))
+        };
+
+        assert_eq!(run(1), run(4));
+    }
+
+    #[test]
+    fn walks_take_every_step() {
+        // 66 steps are the bits of one u64 and the low two bits of the next
+        let walk = RandomWalk::new(66);
+        let n = 66.0_f64;
+        let scale = (2.0 * n).sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (32.0 * n * n));
+        let mut rng = stream_rng(4, 0);
+        for _ in 0..1000 {
+            let mut bits = rng.clone();
+            let rights = bits.next_u64().count_ones() + (bits.next_u64() & 3).count_ones();
+            let distance = (2 * rights as i64 - 66).abs() as f64;
+            assert_eq!((walk.trial(&mut rng) * scale).round(), distance);
+        }
+
+        let estimate = estimate(&RandomWalk::new(100), SAMPLES, 6);
+        assert!(
+            (estimate.pi - PI).abs() <= 5.0 * estimate.std_error,
+            "{:?}",
+            estimate
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "even number of steps")]
+    fn walks_need_an_even_number_of_steps() {
+        RandomWalk::new(65);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 64")]
+    fn walks_need_enough_steps() {
+        RandomWalk::new(2);
+    }
+}
@@ -0,0 +1,253 @@
+// Plain Monte Carlo integration of f over a hyper-rectangle: the integral is
+// the domain's volume times the mean of f at uniform random points, and its
+// standard error is the volume times the standard deviation of f over sqrt(n).
+// Points are drawn in chunks from their own ChaCha streams (see `stream_rng`)
+// and the per-chunk sums are merged in chunk order, so a seed gives the same
+// result for any number of threads.
+
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+use crate::{sampler::stream_rng, stats::z_score};
+
+// trials run per RNG stream
+pub(crate) const CHUNK: u64 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Domain {
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+}
+
+impl Domain {
+    // the box with the given (lower, upper) bounds in each dimension
+    pub fn new(bounds: &[(f64, f64)]) -> Self {
+        assert!(!bounds.is_empty());
+        assert!(bounds.iter().all(|(lo, hi)| lo < hi));
+
+        Self {
+            lower: bounds.iter().map(|b| b.0).collect(),
+            upper: bounds.iter().map(|b| b.1).collect(),
+        }
+    }
+
+    // [lower, upper]^dims
+    pub fn cube(dims: usize, lower: f64, upper: f64) -> Self {
+        Self::new(&vec![(lower, upper); dims])
+    }
+
+    pub fn dims(&self) -> usize {
+        self.lower.len()
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.lower
+            .iter()
+            .zip(&self.upper)
+            .map(|(lo, hi)| hi - lo)
+            .product()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Integral {
+    pub value: f64,
+    pub std_error: f64,
+    pub samples: u64,
+}
+
+impl Integral {
+    // half the width of the two-sided confidence interval at `confidence`
+    pub fn half_width(&self, confidence: f64) -> f64 {
+        z_score(confidence) * self.std_error
+    }
+}
+
+// running mean and sum of squared deviations (Welford), mergeable across chunks
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Moments {
+    pub(crate) n: u64,
+    pub(crate) mean: f64,
+    pub(crate) m2: f64,
+}
+
+impl Moments {
+    pub(crate) fn push(&mut self, x: f64) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    pub(crate) fn merge(self, other: Moments) -> Moments {
+        if self.n == 0 {
+            return other;
+        }
+
+        let n = self.n + other.n;
+        let delta = other.mean - self.mean;
+        Moments {
+            n,
+            mean: self.mean + delta * other.n as f64 / n as f64,
+            m2: self.m2 + other.m2 + delta * delta * (self.n * other.n) as f64 / n as f64,
+        }
+    }
+}
+
+// the moments of `samples` trials, run in parallel chunks of CHUNK trials
+// that each draw from their own stream of `seed` and merged in chunk order.
+// `scratch` makes the state each chunk's trials reuse
+pub(crate) fn run_trials<S, I, T>(samples: u64, seed: u64, scratch: I, trial: T) -> Moments
+where
+    I: Fn() -> S + Sync,
+    T: Fn(&mut S, &mut ChaCha8Rng) -> f64 + Sync,
+{
+    let chunks: Vec<Moments> = (0..samples.div_ceil(CHUNK))
+        .into_par_iter()
+        .map(|chunk| {
+            let mut rng = stream_rng(seed, chunk);
+            let mut state = scratch();
+            let mut moments = Moments::default();
+
+            for _ in chunk * CHUNK..samples.min((chunk + 1) * CHUNK) {
+                moments.push(trial(&mut state, &mut rng));
+            }
+            moments
+        })
+        .collect();
+
+    chunks.into_iter().fold(Moments::default(), Moments::merge)
+}
+
+// integrates `f` over `domain` from `samples` uniform points, at least two so
+// there is a standard error
+pub fn integrate<F>(f: F, domain: &Domain, samples: u64, seed: u64) -> Integral
+where
+    F: Fn(&[f64]) -> f64 + Sync,
+{
+    assert!(samples > 1);
+
+    let moments = run_trials(
+        samples,
+        seed,
+        || vec![0.0; domain.dims()],
+        |point, rng| {
+            for (d, x) in point.iter_mut().enumerate() {
+                *x = rng.gen_range(domain.lower[d]..domain.upper[d]);
+            }
+            f(point)
+        },
+    );
+    let volume = domain.volume();
+    let variance = moments.m2 / (moments.n - 1) as f64;
+
+    Integral {
+        value: volume * moments.mean,
+        std_error: volume * (variance / moments.n as f64).sqrt(),
+        samples: moments.n,
+    }
+}
+
+// pi as the area of the unit disc inside [-1, 1]^2
+pub fn pi(samples: u64, seed: u64) -> Integral {
+    ball_volume(2, samples, seed)
+}
+
+// volume of the unit ball in `dims` dimensions, from the fraction of
+// [-1, 1]^dims it covers
+pub fn ball_volume(dims: usize, samples: u64, seed: u64) -> Integral {
+    let inside = |x: &[f64]| {
+        let r2: f64 = x.iter().map(|x| x * x).sum();
+        if r2 <= 1.0 {
+            1.0
+        } else {
+            0.0
+        }
+    };
+
+    integrate(inside, &Domain::cube(dims, -1.0, 1.0), samples, seed)
+}
+
+// pi^(n/2) / Gamma(n/2 + 1), from V_n = V_{n-2} * 2 pi / n
+pub fn exact_ball_volume(dims: usize) -> f64 {
+    match dims {
+        0 => 1.0,
+        1 => 2.0,
+        n => exact_ball_volume(n - 2) * 2.0 * std::f64::consts::PI / n as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    const SAMPLES: u64 = 200_000;
+
+    fn assert_close(integral: Integral, exact: f64) {
+        let error = (integral.value - exact).abs();
+        assert!(
+            error <= 5.0 * integral.std_error,
+            "{:?} is {} away from {}",
+            integral,
+            error,
+            exact
+        );
+    }
+
+    #[test]
+    fn pi_from_unit_disc() {
+        assert_close(pi(SAMPLES, 1), PI);
+    }
+
+    #[test]
+    fn ball_volumes() {
+        for dims in 1..=6 {
+            assert_close(
+                ball_volume(dims, SAMPLES, dims as u64),
+                exact_ball_volume(dims),
+            );
+        }
+        assert!((exact_ball_volume(3) - 4.0 / 3.0 * PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn known_integrals() {
+        let unit = Domain::cube(1, 0.0, 1.0);
+        assert_close(integrate(|x| x[0] * x[0], &unit, SAMPLES, 2), 1.0 / 3.0);
+
+        let half_period = Domain::new(&[(0.0, PI)]);
+        assert_close(integrate(|x| x[0].sin(), &half_period, SAMPLES, 3), 2.0);
+
+        let cube = Domain::cube(3, 0.0, 1.0);
+        assert_close(integrate(|x| x[0] * x[1] * x[2], &cube, SAMPLES, 4), 0.125);
+
+        // the tails beyond +-6 are below 1e-15
+        let plane = Domain::new(&[(-6.0, 6.0), (-6.0, 6.0)]);
+        let gaussian = |x: &[f64]| (-(x[0] * x[0] + x[1] * x[1])).exp();
+        assert_close(integrate(gaussian, &plane, SAMPLES, 5), PI);
+    }
+
+    #[test]
+    fn constant_has_no_error() {
+        let domain = Domain::new(&[(0.0, 2.0), (1.0, 4.0)]);
+        let integral = integrate(|_| 1.5, &domain, 1000, 0);
+
+        assert!((integral.value - 9.0).abs() < 1e-12);
+        assert!(integral.std_error < 1e-12);
+    }
+
+    #[test]
+    fn seeded_result_does_not_depend_on_threads() {
+        let run = |threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| ball_volume(4, 5 * CHUNK + 3, 11))
+        };
+
+        assert_eq!(run(1), run(4));
+    }
+}
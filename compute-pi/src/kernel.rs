@@ -0,0 +1,207 @@
+// Counting kernel shared by the estimators: how many of the points
+// (xs[i], ys[i]) land inside the unit quarter circle. Every backend computes
+// x * x + y * y <= 1.0 in f32 without fused multiply-adds, so they all agree
+// on the same inputs.
+
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+// A way of running the kernel. The SIMD ones can only be made by `available`
+// and `detect`, after checking that the CPU supports them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backend(Kind);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Scalar,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+    #[cfg(target_arch = "x86_64")]
+    Sse,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+}
+
+impl Backend {
+    pub const SCALAR: Backend = Backend(Kind::Scalar);
+
+    // every backend this machine supports, fastest first
+    pub fn available() -> Vec<Self> {
+        let mut backends = Vec::new();
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                backends.push(Backend(Kind::Neon));
+            }
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                backends.push(Backend(Kind::Avx2));
+            }
+            // SSE2 is part of the x86_64 baseline
+            backends.push(Backend(Kind::Sse));
+        }
+
+        backends.push(Self::SCALAR);
+        backends
+    }
+
+    // the fastest backend this machine supports
+    pub fn detect() -> Self {
+        Self::available()[0]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.0 {
+            Kind::Scalar => "scalar",
+            #[cfg(target_arch = "aarch64")]
+            Kind::Neon => "neon",
+            #[cfg(target_arch = "x86_64")]
+            Kind::Sse => "sse",
+            #[cfg(target_arch = "x86_64")]
+            Kind::Avx2 => "avx2",
+        }
+    }
+
+    pub fn count_inside(self, xs: &[f32], ys: &[f32]) -> u32 {
+        assert!(xs.len() == ys.len());
+
+        match self.0 {
+            Kind::Scalar => count_inside_scalar(xs, ys),
+            // SAFETY: a SIMD `Kind` is only ever wrapped in a `Backend` by
+            // `available`, after checking the CPU supports it
+            #[cfg(target_arch = "aarch64")]
+            Kind::Neon => unsafe { count_inside_neon(xs, ys) },
+            #[cfg(target_arch = "x86_64")]
+            Kind::Sse => unsafe { count_inside_sse(xs, ys) },
+            #[cfg(target_arch = "x86_64")]
+            Kind::Avx2 => unsafe { count_inside_avx2(xs, ys) },
+        }
+    }
+}
+
+fn count_inside_scalar(xs: &[f32], ys: &[f32]) -> u32 {
+    xs.iter()
+        .zip(ys)
+        .filter(|(x, y)| *x * *x + *y * *y <= 1.0)
+        .count() as u32
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn count_inside_neon(xs: &[f32], ys: &[f32]) -> u32 {
+    let chunks = xs.len() / 4;
+    let mut count = 0;
+
+    for i in 0..chunks {
+        let a = vld1q_f32(xs.as_ptr().add(4 * i));
+        let b = vld1q_f32(ys.as_ptr().add(4 * i));
+
+        let rad = vmlaq_f32(vmulq_f32(a, a), b, b);
+
+        let less_than_or_equal = vcleq_f32(rad, vdupq_n_f32(1.0));
+        let result_as_one_zero = vshrq_n_u32(less_than_or_equal, 31);
+
+        let pairwise_sum = vpadd_u32(
+            vget_low_u32(result_as_one_zero),
+            vget_high_u32(result_as_one_zero),
+        );
+        count += vget_lane_u32(pairwise_sum, 0) + vget_lane_u32(pairwise_sum, 1);
+    }
+
+    count + count_inside_scalar(&xs[4 * chunks..], &ys[4 * chunks..])
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn count_inside_sse(xs: &[f32], ys: &[f32]) -> u32 {
+    let chunks = xs.len() / 4;
+    let one = _mm_set1_ps(1.0);
+    let mut count = 0;
+
+    for i in 0..chunks {
+        let a = _mm_loadu_ps(xs.as_ptr().add(4 * i));
+        let b = _mm_loadu_ps(ys.as_ptr().add(4 * i));
+
+        let rad = _mm_add_ps(_mm_mul_ps(a, a), _mm_mul_ps(b, b));
+
+        // one bit per lane that is inside the circle
+        let mask = _mm_movemask_ps(_mm_cmple_ps(rad, one));
+        count += mask.count_ones();
+    }
+
+    count + count_inside_scalar(&xs[4 * chunks..], &ys[4 * chunks..])
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn count_inside_avx2(xs: &[f32], ys: &[f32]) -> u32 {
+    let chunks = xs.len() / 8;
+    let one = _mm256_set1_ps(1.0);
+    let mut count = 0;
+
+    for i in 0..chunks {
+        let a = _mm256_loadu_ps(xs.as_ptr().add(8 * i));
+        let b = _mm256_loadu_ps(ys.as_ptr().add(8 * i));
+
+        let rad = _mm256_add_ps(_mm256_mul_ps(a, a), _mm256_mul_ps(b, b));
+
+        let mask = _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_LE_OQ>(rad, one));
+        count += mask.count_ones();
+    }
+
+    count + count_inside_scalar(&xs[8 * chunks..], &ys[8 * chunks..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn every_backend_agrees_with_scalar() {
+        let mut rng = ChaCha8Rng::seed_from_u64(34);
+        let mut xs: Vec<f32> = (0..1000).map(|_| rng.gen()).collect();
+        let mut ys: Vec<f32> = (0..1000).map(|_| rng.gen()).collect();
+
+        // points on and just either side of the circle
+        for (i, (x, y)) in [
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.6, 0.8),
+            (0.8, 0.6),
+            (0.70710677, 0.70710677),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            xs[i] = x;
+            ys[i] = y;
+            xs[i + 5] = f32::from_bits(x.to_bits() + 1);
+            ys[i + 5] = y;
+        }
+
+        let backends = Backend::available();
+        assert_eq!(backends.last(), Some(&Backend::SCALAR));
+
+        // every length up to a few SIMD widths, to cover the scalar tails
+        for len in (0..40).chain([999, 1000]) {
+            let expected = count_inside_scalar(&xs[..len], &ys[..len]);
+            for backend in &backends {
+                assert_eq!(
+                    backend.count_inside(&xs[..len], &ys[..len]),
+                    expected,
+                    "{} backend, {} points",
+                    backend.name(),
+                    len
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+pub mod bbp;
+pub mod checkpoint;
+pub mod digits;
+pub mod distributed;
+pub mod geometric;
+pub mod integrate;
+pub mod kernel;
+pub mod lanes;
+pub mod methods;
+pub mod progress;
+pub mod sampler;
+pub mod stats;
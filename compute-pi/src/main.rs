@@ -1,167 +1,1088 @@
-// #![feature(neon)]
-use std::arch::aarch64::*;
+use std::{
+    net::TcpListener,
+    time::{Duration, Instant},
+};
 
+use clap::{Parser, Subcommand, ValueEnum};
+use compute_pi::{
+    bbp,
+    checkpoint::Checkpoint,
+    digits,
+    distributed::{self, Coordinator, Task},
+    geometric::{self, Experiment},
+    kernel::Backend,
+    methods::{self, Generator, Run, BATCH, CHUNK},
+    progress::Progress,
+    sampler::Sampler,
+    stats::Estimate,
+};
 use rand::prelude::*;
 use rayon::prelude::*;
 
-fn compute_pi_rayon() {
-    // let xs: Vec<[f32; 4]> = Vec::with_capacity(capacity);
-    // let ys: Vec<[f32; 4]> = Vec::with_capacity(capacity);
-    // let xs: [[f32; 4]; 1024] = [[0.0; 4]; 1024];
-    // let ys: [[f32; 4]; 1024] = [[0.0; 4]; 1024];
-
-    // xs.par_iter().for_each(op)
-    let threads = 16;
-    let iters_to_run = (2 as usize).pow(32);
-    let mut counts: Vec<u32> = vec![0; threads];
-    counts.par_iter_mut().for_each(|count_ptr| {
-        let mut arr_a: [f32; 4] = [0.0; 4];
-        let mut arr_b: [f32; 4] = [0.0; 4];
-        let mut rng = rand::thread_rng();
-
-        let mut count = 0;
-
-        assert!(iters_to_run % 4 == 0);
-
-        for i in 0..(iters_to_run / 4) {
-            if i % 100000000 == 0 {
-                println!(
-                    "Thread {}: {}% done",
-                    rayon::current_thread_index().unwrap(),
-                    (i as f64 / iters_to_run as f64) * 400.0
-                );
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Method {
+    /// batched SIMD kernel on every rayon worker
+    Rayon,
+    /// SIMD kernel, four points at a time on one thread
+    Simd,
+    /// fills large vectors of points, then counts them
+    Vec,
+    /// draws and tests one point at a time
+    Loop,
+}
 
-            arr_a = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
-            arr_b = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
-            let mut sum: u32 = 0;
-            unsafe {
-                let a = vld1q_f32(&arr_a as *const f32);
-                let b = vld1q_f32(&arr_b as *const f32);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DigitsAlgorithm {
+    /// Chudnovsky series with binary splitting
+    Chudnovsky,
+    /// Machin's arctangent formula
+    Machin,
+}
 
-                let rad = vmlaq_f32(vmulq_f32(a, a), b, b);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// human-readable lines
+    Text,
+    /// one JSON object per line, for logging and plotting
+    Json,
+}
 
-                let less_than_or_equal = vcleq_f32(rad, vdupq_n_f32(1.0));
-                let result_as_one_zero = vshrq_n_u32(less_than_or_equal, 31);
+#[derive(Subcommand)]
+enum Command {
+    /// compute decimal digits of pi exactly instead of estimating it
+    Digits {
+        /// number of decimals to compute
+        digits: usize,
 
-                let pairwise_sum = vpadd_u32(
-                    vget_low_u32(result_as_one_zero),
-                    vget_high_u32(result_as_one_zero),
-                );
-                sum = vget_lane_u32(pairwise_sum, 0) + vget_lane_u32(pairwise_sum, 1);
-            }
+        #[clap(short, long, value_enum, default_value_t = DigitsAlgorithm::Chudnovsky)]
+        algorithm: DigitsAlgorithm,
 
-            count += sum;
-        }
+        /// check the digits against the bundled first 10000 decimals
+        #[clap(long)]
+        verify: bool,
+    },
+    /// hex digits of pi from any position, without the digits before it
+    Hex {
+        /// position of the first digit (1 is the first digit after the point)
+        #[clap(value_parser = clap::value_parser!(u64).range(1..=bbp::MAX_POSITION))]
+        position: u64,
 
-        *count_ptr = count;
-    });
+        /// number of digits to compute
+        #[clap(short = 'n', long, default_value_t = 16)]
+        count: usize,
+
+        /// check the digits against the bundled first 1000 hex digits
+        #[clap(long)]
+        verify: bool,
+    },
+    /// hand out the run's chunks to workers over TCP and report the estimate
+    Coordinate {
+        /// address to listen on for workers
+        #[clap(long, default_value = "127.0.0.1:7878")]
+        listen: String,
+
+        /// samples per task handed to a worker
+        #[clap(long, default_value_t = 1 << 28)]
+        task_samples: u64,
 
-    let approx = counts
-        .iter()
-        .map(|e| 4.0 * (*e as f64) / (threads * iters_to_run) as f64)
-        .sum::<f64>();
+        /// seconds a worker gets to answer a task before it goes to another
+        #[clap(long, default_value_t = distributed::DEFAULT_TASK_TIMEOUT.as_secs(), value_parser = clap::value_parser!(u64).range(1..))]
+        task_timeout: u64,
+    },
+    /// count chunks for a coordinator until it has no more work
+    Work {
+        /// address of the coordinator
+        #[clap(default_value = "127.0.0.1:7878")]
+        connect: String,
+    },
+    /// estimate pi with other random experiments, like Buffon's needle
+    Geometric {
+        /// experiments to run (all of them if none are given)
+        #[clap(value_enum)]
+        experiments: Vec<Experiment>,
 
-    println!("PI ({:?}): {:?}", threads * iters_to_run, approx);
+        /// trials per experiment (at least 2, for a standard error)
+        #[clap(short = 'n', long, default_value_t = 1 << 24, value_parser = clap::value_parser!(u64).range(2..))]
+        samples: u64,
+    },
+    /// compare the error of every sampler as the sample count grows
+    Compare {
+        /// largest sample count to try
+        #[clap(short = 'n', long, default_value_t = 1 << 24)]
+        max_samples: u64,
+
+        /// runs per sampler and sample count, with consecutive seeds
+        #[clap(short, long, default_value_t = 16)]
+        repeats: u64,
+    },
 }
 
-fn compute_pi_simd() {
-    let mut total = 1;
-    let mut count = 1;
-    let mut rng = rand::thread_rng();
-    let mut arr_a: [f32; 4] = [0.0; 4];
-    let mut arr_b: [f32; 4] = [0.0; 4];
-    unsafe {
-        while total < 1e9 as u32 {
-            arr_a = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
-            arr_b = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+#[derive(Parser)]
+struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
 
-            let a = vld1q_f32(&arr_a as *const f32);
-            let b = vld1q_f32(&arr_b as *const f32);
+    #[clap(short, long, value_enum, default_value_t = Method::Rayon)]
+    method: Method,
 
-            let rad = vmlaq_f32(vmulq_f32(a, a), b, b);
+    /// how the rayon method places its points. The confidence interval
+    /// assumes independent uniform points, so it overstates the error of the
+    /// other samplers
+    #[clap(long, value_enum, default_value_t = Sampler::Uniform)]
+    sampler: Sampler,
 
-            let less_than_or_equal = vcleq_f32(rad, vdupq_n_f32(1.0));
-            let result_as_one_zero = vshrq_n_u32(less_than_or_equal, 31);
+    /// random number generator for the rayon method's points
+    #[clap(long, value_enum, default_value_t = Generator::Chacha)]
+    rng: Generator,
 
-            let pairwise_sum = vpadd_u32(
-                vget_low_u32(result_as_one_zero),
-                vget_high_u32(result_as_one_zero),
-            );
-            let sum = vget_lane_u32(pairwise_sum, 0) + vget_lane_u32(pairwise_sum, 1);
-
-            assert!(sum <= 4);
-            count += sum;
-            total += 4;
-
-            if total % 50000000 < 4 {
-                println!(
-                    "PI ({:?}): {:?}",
-                    total,
-                    (count as f64 / total as f64) * 4.0
-                );
+    /// total number of points to sample
+    #[clap(short = 'n', long, default_value_t = 1 << 32, value_parser = clap::value_parser!(u64).range(1..))]
+    samples: u64,
+
+    /// worker threads for the rayon method (0 = one per core)
+    #[clap(short, long, global = true, default_value_t = 0)]
+    threads: usize,
+
+    /// seed for the random number generators; random (and printed) if not
+    /// given. A seed gives the same estimate for any number of threads
+    #[clap(short, long, global = true)]
+    seed: Option<u64>,
+
+    /// don't show the progress line or status messages on stderr; results
+    /// are always printed
+    #[clap(short, long, global = true)]
+    quiet: bool,
+
+    /// how to print the results on stdout
+    #[clap(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// confidence level of the reported interval, between 0 and 1
+    #[clap(short, long, default_value_t = 0.99, value_parser = parse_confidence)]
+    confidence: f64,
+
+    /// instead of a fixed sample count, sample until the confidence interval
+    /// is within +-PRECISION (rayon method only)
+    #[clap(long, value_parser = parse_precision)]
+    precision: Option<f64>,
+
+    /// save the rayon method's progress to this file as it goes (fixed-size
+    /// runs only)
+    #[clap(long, conflicts_with = "precision")]
+    checkpoint: Option<String>,
+
+    /// samples between checkpoints
+    #[clap(long, default_value_t = 1 << 32)]
+    checkpoint_every: u64,
+
+    /// continue the run saved in the checkpoint file, with its seed and
+    /// sample count
+    #[clap(long, requires = "checkpoint")]
+    resume: bool,
+}
+
+// chunks sampled before the first check for convergence
+const MIN_CONVERGE_CHUNKS: u64 = 64;
+
+// the most chunks whose samples still fit in a u64
+const MAX_CHUNKS: u64 = u64::MAX / CHUNK;
+
+fn parse_confidence(s: &str) -> Result<f64, String> {
+    let confidence: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if confidence > 0.0 && confidence < 1.0 {
+        Ok(confidence)
+    } else {
+        Err("must be strictly between 0 and 1".to_string())
+    }
+}
+
+fn parse_precision(s: &str) -> Result<f64, String> {
+    let precision: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if precision > 0.0 && precision.is_finite() {
+        Ok(precision)
+    } else {
+        Err("must be a positive number".to_string())
+    }
+}
+
+// how the rayon method draws its points, as recorded in checkpoints
+fn settings(args: &Args) -> String {
+    let generator = args.rng.to_possible_value().unwrap();
+    format!("{} {}", args.sampler.name(), generator.get_name())
+}
+
+// counts the chunks a round at a time, saving a checkpoint after each round
+// if asked to. `resumed` skips the rounds a previous run already finished
+fn compute_pi_rayon(
+    args: &Args,
+    seed: u64,
+    resumed: Option<Checkpoint>,
+    progress: &Progress,
+) -> Estimate {
+    let run = Run::new(args.sampler, args.rng, seed);
+    progress.note(&format!(
+        "Using {} kernel on {} threads",
+        run.backend.name(),
+        rayon::current_num_threads()
+    ));
+
+    let mut state = resumed.unwrap_or_else(|| Checkpoint::new(seed, args.samples, settings(args)));
+    let samples = state.samples;
+    let chunks = samples.div_ceil(CHUNK);
+    let chunks_per_round = match args.checkpoint {
+        Some(_) => (args.checkpoint_every / CHUNK).max(1),
+        None => chunks,
+    };
+
+    // a resumed run starts the line where the previous run left off
+    progress.set_total(samples);
+    progress.restore(samples.min(state.chunks_done * CHUNK), state.inside);
+
+    while state.chunks_done < chunks {
+        let end = chunks.min(state.chunks_done + chunks_per_round);
+        state.inside += run.count_chunks(state.chunks_done..end, samples, progress);
+        state.chunks_done = end;
+
+        if let Some(path) = &args.checkpoint {
+            if let Err(e) = state.save(path) {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
             }
+            progress.note(&format!("Checkpoint: {}/{} chunks done", end, chunks));
+        }
+    }
+
+    Estimate::new(state.inside, samples)
+}
+
+fn coordinate(
+    args: &Args,
+    seed: u64,
+    listen: &str,
+    task_samples: u64,
+    task_timeout: u64,
+    progress: &Progress,
+) -> Result<Estimate, String> {
+    let listener = TcpListener::bind(listen).map_err(|e| format!("{}: {}", listen, e))?;
+    progress.note(&format!("Waiting for workers on {}", listen));
+
+    let coordinator = Coordinator::new(
+        seed,
+        args.samples,
+        settings(args),
+        args.samples.div_ceil(CHUNK),
+        (task_samples / CHUNK).max(1),
+    )
+    .task_timeout(Duration::from_secs(task_timeout));
+    let inside = coordinator.run(listener).map_err(|e| e.to_string())?;
+
+    Ok(Estimate::new(inside, args.samples))
+}
+
+// counts one task from a coordinator with the sampler and generator it names
+fn count_task(task: &Task) -> Result<u64, String> {
+    let (sampler, generator) = task
+        .settings
+        .split_once(' ')
+        .and_then(|(sampler, generator)| {
+            Some((
+                Sampler::from_str(sampler, false).ok()?,
+                Generator::from_str(generator, false).ok()?,
+            ))
+        })
+        .ok_or_else(|| format!("unknown settings {:?}", task.settings))?;
+
+    let run = Run::new(sampler, generator, task.seed);
+    Ok(run.count_chunks(task.chunks.clone(), task.samples, &Progress::new(0, false)))
+}
+
+fn work(connect: &str, format: Format, progress: &Progress) -> Result<(), String> {
+    let tasks = distributed::run_worker(connect, |task| {
+        progress.note(&format!("Counting chunks {:?}", task.chunks));
+        count_task(task).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    })
+    .map_err(|e| format!("{}: {}", connect, e))?;
+
+    match format {
+        Format::Text => progress.note(&format!("Counted {} tasks", tasks)),
+        Format::Json => println!("{{\"connect\":{:?},\"tasks\":{}}}", connect, tasks),
+    }
+    Ok(())
+}
+
+// the checkpoint to resume from, checked against the current settings
+fn load_checkpoint(args: &Args) -> Result<Checkpoint, String> {
+    let path = args.checkpoint.as_ref().unwrap();
+    let checkpoint = Checkpoint::load(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    if args.method != Method::Rayon || args.precision.is_some() {
+        return Err("only fixed-size runs of the rayon method can be resumed".to_string());
+    }
+    if checkpoint.settings != settings(args) {
+        return Err(format!(
+            "{}: saved with settings \"{}\", not \"{}\"",
+            path,
+            checkpoint.settings,
+            settings(args)
+        ));
+    }
+    if args.seed.is_some_and(|seed| seed != checkpoint.seed) {
+        return Err(format!("{}: saved with seed {}", path, checkpoint.seed));
+    }
+
+    Ok(checkpoint)
+}
+
+// keeps adding chunks until the confidence interval is within +-`precision`.
+// Each round projects the samples still needed from the current estimate, so
+// the result only depends on the seed, not on the threads
+fn converge_pi_rayon(args: &Args, seed: u64, precision: f64, progress: &Progress) -> Estimate {
+    let run = Run::new(args.sampler, args.rng, seed);
+    progress.note(&format!(
+        "Using {} kernel on {} threads",
+        run.backend.name(),
+        rayon::current_num_threads()
+    ));
+
+    let mut estimate = Estimate::default();
+    let mut chunks_done = 0;
+    let mut target = MIN_CONVERGE_CHUNKS;
+    let max_chunks = max_chunks(args.sampler);
+
+    loop {
+        progress.set_total(target * CHUNK);
+        estimate.inside += run.count_chunks(chunks_done..target, target * CHUNK, progress);
+        estimate.samples = target * CHUNK;
+        chunks_done = target;
+
+        let half_width = estimate.half_width(args.confidence);
+        if half_width <= precision {
+            return estimate;
+        }
+        if chunks_done == max_chunks {
+            progress.note(&format!(
+                "Stopped at {} samples: +-{:e}",
+                estimate.samples, half_width
+            ));
+            return estimate;
         }
+
+        let needed = estimate.samples_for(precision, args.confidence);
+        progress.note(&format!(
+            "{} samples: +-{:e}, need about {} samples",
+            estimate.samples, half_width, needed
+        ));
+        target = next_target(needed, chunks_done, max_chunks);
+    }
+}
+
+// the chunk count to sample up to next, always past `chunks_done` but never
+// past `max_chunks`
+fn next_target(needed: u64, chunks_done: u64, max_chunks: u64) -> u64 {
+    needed.div_ceil(CHUNK).clamp(chunks_done + 1, max_chunks)
+}
+
+// the most chunks a run of `sampler` can count, either before its points
+// repeat or before the sample count overflows
+fn max_chunks(sampler: Sampler) -> u64 {
+    sampler.period().map_or(MAX_CHUNKS, |period| period / CHUNK)
+}
+
+// fixed-size runs of the rayon method can't take more points than the
+// sampler has
+fn check_samples(args: &Args) -> Result<(), String> {
+    let rayon = match args.command {
+        None => args.method == Method::Rayon,
+        Some(Command::Coordinate { .. }) => true,
+        Some(_) => false,
+    };
+    match args.sampler.period() {
+        Some(period) if rayon && args.precision.is_none() && args.samples > period => Err(format!(
+            "the {} sampler only has {} points",
+            args.sampler.name(),
+            period
+        )),
+        _ => Ok(()),
     }
 }
 
-fn compute_pi() {
-    let mut total = 1;
-    let mut count = 1;
-    let mut xs: Vec<f32> = vec![0.0; 33554432];
-    let mut ys: Vec<f32> = vec![0.0; 33554432];
-    let mut rng = rand::thread_rng();
+// root mean square error of every sampler at sample counts 2^10, 2^12, ...
+// up to `max_samples`, each over `repeats` seeds
+fn compare_samplers(
+    max_samples: u64,
+    repeats: u64,
+    generator: Generator,
+    seed: u64,
+    format: Format,
+) {
+    if format == Format::Text {
+        print!("{:>12}", "samples");
+        for sampler in Sampler::ALL {
+            print!("{:>12}", sampler.name());
+        }
+        println!();
+    }
 
-    while total < 1e9 as u32 {
-        for i in 0..xs.len() {
-            xs[i] = rng.gen();
-            ys[i] = rng.gen();
+    let mut samples = BATCH as u64;
+    while samples <= max_samples {
+        if format == Format::Text {
+            print!("{:>12}", samples);
         }
+        for sampler in Sampler::ALL {
+            if sampler.period().is_some_and(|period| samples > period) {
+                if format == Format::Text {
+                    print!("{:>12}", "-");
+                }
+                continue;
+            }
+            let squared_error: f64 = (0..repeats)
+                .into_par_iter()
+                .map(|r| {
+                    let seed = seed.wrapping_add(r);
+                    let run = Run::new(sampler, generator, seed);
+                    let chunks = 0..samples.div_ceil(CHUNK);
+                    let inside = run.count_chunks(chunks, samples, &Progress::new(0, false));
+                    (Estimate::new(inside, samples).pi() - std::f64::consts::PI).powi(2)
+                })
+                .sum();
+            let rms_error = (squared_error / repeats as f64).sqrt();
 
-        let mut inside = 0;
-        for i in 0..xs.len() {
-            if xs[i].powi(2) + ys[i].powi(2) <= 1.0 {
-                inside += 1;
+            match format {
+                Format::Text => print!("{:>12.3e}", rms_error),
+                Format::Json => println!(
+                    "{{\"seed\":{},\"sampler\":\"{}\",\"samples\":{},\"repeats\":{},\"rms_error\":{}}}",
+                    seed,
+                    sampler.name(),
+                    samples,
+                    repeats,
+                    json_number(rms_error)
+                ),
             }
         }
+        if format == Format::Text {
+            println!();
+        }
+
+        samples *= 4;
+    }
+}
+
+// runs each experiment with the same seed and prints its estimate
+fn run_experiments(experiments: &[Experiment], samples: u64, args: &Args, seed: u64) {
+    let experiments = if experiments.is_empty() {
+        &Experiment::ALL[..]
+    } else {
+        experiments
+    };
+
+    for experiment in experiments {
+        let start = Instant::now();
+        let estimate = geometric::estimate(experiment.estimator(), samples, seed);
+        let seconds = start.elapsed().as_secs_f64();
+        let half_width = estimate.half_width(args.confidence);
+        let error = estimate.pi - std::f64::consts::PI;
+
+        match args.format {
+            Format::Text => println!(
+                "{:>8}: {:?} +-{:.3e} (error {:+.3e}, {:.1} M trials/s)",
+                experiment.name(),
+                estimate.pi,
+                half_width,
+                error,
+                samples as f64 / seconds / 1e6
+            ),
+            Format::Json => println!(
+                concat!(
+                    "{{\"experiment\":\"{}\",\"seed\":{},\"samples\":{},\"pi\":{},",
+                    "\"std_error\":{},\"confidence\":{},\"ci_low\":{},\"ci_high\":{},",
+                    "\"error\":{},\"seconds\":{}}}"
+                ),
+                experiment.name(),
+                seed,
+                estimate.samples,
+                json_number(estimate.pi),
+                json_number(estimate.std_error),
+                json_number(args.confidence),
+                json_number(estimate.pi - half_width),
+                json_number(estimate.pi + half_width),
+                json_number(error),
+                json_number(seconds)
+            ),
+        }
+    }
+}
 
-        total += xs.len() as u32;
-        count += inside;
+// a JSON number, or null for the infinities and NaN JSON can't represent
+fn json_number(x: f64) -> String {
+    if x.is_finite() {
+        format!("{:?}", x)
+    } else {
+        "null".to_string()
+    }
+}
+
+// the run's result as one line of JSON
+fn json_result(args: &Args, seed: u64, estimate: &Estimate, seconds: f64) -> String {
+    let method = args.method.to_possible_value().unwrap();
+    let method = match args.command {
+        Some(Command::Coordinate { .. }) => "coordinate",
+        _ => method.get_name(),
+    };
+    let rng = args.rng.to_possible_value().unwrap();
+    let approx = estimate.pi();
+    let half_width = estimate.half_width(args.confidence);
 
+    format!(
+        concat!(
+            "{{\"method\":\"{}\",\"sampler\":\"{}\",\"rng\":\"{}\",\"seed\":{},",
+            "\"samples\":{},\"inside\":{},\"pi\":{},\"std_error\":{},",
+            "\"confidence\":{},\"ci_low\":{},\"ci_high\":{},\"error\":{},",
+            "\"precision\":{},\"seconds\":{},\"samples_per_second\":{}}}"
+        ),
+        method,
+        args.sampler.name(),
+        rng.get_name(),
+        seed,
+        estimate.samples,
+        estimate.inside,
+        json_number(approx),
+        json_number(estimate.std_error()),
+        json_number(args.confidence),
+        json_number(approx - half_width),
+        json_number(approx + half_width),
+        json_number(approx - std::f64::consts::PI),
+        args.precision.map_or("null".to_string(), json_number),
+        json_number(seconds),
+        json_number(estimate.samples as f64 / seconds)
+    )
+}
+
+fn print_result(args: &Args, estimate: &Estimate, elapsed: Duration) {
+    let approx = estimate.pi();
+    let half_width = estimate.half_width(args.confidence);
+    println!("PI ({:?}): {:?}", estimate.samples, approx);
+    println!("Std error: {:e}", estimate.std_error());
+    println!(
+        "{}% CI: [{:?}, {:?}] (+-{:e})",
+        args.confidence * 100.0,
+        approx - half_width,
+        approx + half_width,
+        half_width
+    );
+    println!("Error: {:e}", approx - std::f64::consts::PI);
+    if let Some(precision) = args.precision {
         println!(
-            "PI ({:?}): {:?}",
-            total,
-            (count as f64 / total as f64) * 4.0
+            "Reached +-{:e} at {}% after {} samples",
+            precision,
+            args.confidence * 100.0,
+            estimate.samples
         );
     }
+    println!(
+        "Throughput: {:.1} M samples/s ({:.2?})",
+        estimate.samples as f64 / elapsed.as_secs_f64() / 1e6,
+        elapsed
+    );
+}
+
+// the digits as one line of JSON; `verified` is how many were checked
+// against the known digits, if asked to
+fn json_digits(
+    algorithm: DigitsAlgorithm,
+    pi: &str,
+    seconds: f64,
+    verified: Option<usize>,
+) -> String {
+    format!(
+        "{{\"algorithm\":\"{}\",\"digits\":{},\"pi\":\"{}\",\"seconds\":{},\"verified\":{}}}",
+        algorithm.to_possible_value().unwrap().get_name(),
+        pi.len() - 2,
+        pi,
+        json_number(seconds),
+        verified.map_or("null".to_string(), |n| n.to_string())
+    )
 }
 
-fn compute_pi_b() {
-    let mut total = 1;
-    let mut count = 1;
-    let mut rng = rand::thread_rng();
+fn compute_digits(
+    digits: usize,
+    algorithm: DigitsAlgorithm,
+    verify: bool,
+    format: Format,
+    progress: &Progress,
+) {
+    let start = Instant::now();
+    let pi = match algorithm {
+        DigitsAlgorithm::Chudnovsky => digits::chudnovsky(digits),
+        DigitsAlgorithm::Machin => digits::machin(digits),
+    };
+    let elapsed = start.elapsed();
+    progress.note(&format!("Computed {} digits in {:.2?}", digits, elapsed));
 
-    while total < 1e9 as u32 {
-        let loop_size = 50000000;
-        for _ in 0..loop_size {
-            let x: f32 = rng.gen();
-            let y: f32 = rng.gen();
+    let verified = verify.then(|| {
+        let known = digits::KNOWN_DIGITS.trim().len() - 2;
+        let checked = digits.min(known);
+        let matching = digits::matching_digits(&pi);
+        if matching < checked {
+            eprintln!("Digit {} does not match the known digits", matching + 1);
+            std::process::exit(1);
+        }
+        checked
+    });
 
-            if x.powi(2) + y.powi(2) <= 1.0 {
-                count += 1;
+    match format {
+        Format::Text => {
+            println!("{}", pi);
+            if let Some(checked) = verified {
+                println!("First {} digits match the known digits", checked);
             }
         }
+        Format::Json => println!(
+            "{}",
+            json_digits(algorithm, &pi, elapsed.as_secs_f64(), verified)
+        ),
+    }
+}
 
-        total += loop_size;
+// the hex digits as one line of JSON, like `json_digits`
+fn json_hex_digits(position: u64, hex: &str, seconds: f64, verified: Option<usize>) -> String {
+    format!(
+        "{{\"position\":{},\"count\":{},\"hex\":\"{}\",\"seconds\":{},\"verified\":{}}}",
+        position,
+        hex.len(),
+        hex,
+        json_number(seconds),
+        verified.map_or("null".to_string(), |n| n.to_string())
+    )
+}
 
-        println!(
-            "PI ({:?}): {:?}",
-            total,
-            (count as f64 / total as f64) * 4.0
+fn compute_hex_digits(
+    position: u64,
+    count: usize,
+    verify: bool,
+    format: Format,
+    progress: &Progress,
+) {
+    if !bbp::in_range(position, count) {
+        eprintln!(
+            "digits can only be computed up to position {}",
+            bbp::MAX_POSITION
         );
+        std::process::exit(1);
+    }
+
+    let start = Instant::now();
+    let hex = bbp::hex_digits(position, count);
+    let elapsed = start.elapsed();
+    progress.note(&format!(
+        "Computed {} hex digits from position {} in {:.2?}",
+        count, position, elapsed
+    ));
+
+    let verified = verify.then(|| {
+        let known = bbp::known_hex_digits(position);
+        if let Some(i) = hex.bytes().zip(known.bytes()).position(|(a, b)| a != b) {
+            eprintln!(
+                "Digit at position {} does not match the known digits",
+                position + i as u64
+            );
+            std::process::exit(1);
+        }
+        count.min(known.len())
+    });
+
+    match format {
+        Format::Text => {
+            println!("{}", hex);
+            if let Some(checked) = verified {
+                println!("{} digits match the known digits", checked);
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            json_hex_digits(position, &hex, elapsed.as_secs_f64(), verified)
+        ),
     }
 }
 
 fn main() {
-    compute_pi_rayon();
+    let args = Args::parse();
+
+    if args.threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(args.threads)
+            .build_global()
+            .expect("failed to configure the rayon thread pool");
+    }
+
+    // status messages for the commands that don't show a progress line
+    let notes = Progress::new(0, !args.quiet);
+
+    if let Some(Command::Hex {
+        position,
+        count,
+        verify,
+    }) = args.command
+    {
+        compute_hex_digits(position, count, verify, args.format, &notes);
+        return;
+    }
+
+    if let Some(Command::Digits {
+        digits,
+        algorithm,
+        verify,
+    }) = args.command
+    {
+        compute_digits(digits, algorithm, verify, args.format, &notes);
+        return;
+    }
+
+    if let Some(Command::Work { connect }) = &args.command {
+        if let Err(e) = work(connect, args.format, &notes) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(e) = check_samples(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let resumed = if args.resume {
+        match load_checkpoint(&args) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let seed = match &resumed {
+        Some(checkpoint) => checkpoint.seed,
+        None => args.seed.unwrap_or_else(random),
+    };
+    if args.format == Format::Text {
+        println!("Seed: {}", seed);
+    }
+
+    if let Some(Command::Compare {
+        max_samples,
+        repeats,
+    }) = args.command
+    {
+        compare_samplers(max_samples, repeats, args.rng, seed, args.format);
+        return;
+    }
+
+    if let Some(Command::Geometric {
+        experiments,
+        samples,
+    }) = &args.command
+    {
+        run_experiments(experiments, *samples, &args, seed);
+        return;
+    }
+
+    let progress = Progress::new(args.samples, !args.quiet);
+    let start = Instant::now();
+    let estimate = progress.show(|| match (&args.command, args.method, args.precision) {
+        (
+            Some(Command::Coordinate {
+                listen,
+                task_samples,
+                task_timeout,
+            }),
+            _,
+            _,
+        ) => coordinate(&args, seed, listen, *task_samples, *task_timeout, &progress)
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }),
+        (_, Method::Rayon, Some(precision)) => converge_pi_rayon(&args, seed, precision, &progress),
+        (_, _, Some(_)) => {
+            eprintln!("--precision is only supported by the rayon method");
+            std::process::exit(1);
+        }
+        (_, Method::Rayon, None) => compute_pi_rayon(&args, seed, resumed, &progress),
+        (_, Method::Simd, None) => {
+            progress.note(&format!("Using {} kernel", Backend::detect().name()));
+            methods::compute_pi_simd(args.samples, seed, &progress)
+        }
+        (_, Method::Vec, None) => methods::compute_pi(args.samples, seed, &progress),
+        (_, Method::Loop, None) => methods::compute_pi_b(args.samples, seed, &progress),
+    });
+    let elapsed = start.elapsed();
+
+    match args.format {
+        Format::Text => print_result(&args, &estimate, elapsed),
+        Format::Json => println!(
+            "{}",
+            json_result(&args, seed, &estimate, elapsed.as_secs_f64())
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn args(extra: &[&str]) -> Args {
+        Args::parse_from(["compute-pi", "--quiet"].iter().chain(extra))
+    }
+
+    fn quiet() -> Progress {
+        Progress::new(0, false)
+    }
+
+    fn assert_close(estimate: &Estimate, samples: u64) {
+        assert_eq!(estimate.samples, samples);
+        let error = (estimate.pi() - PI).abs();
+        assert!(
+            error < 5.0 * estimate.std_error(),
+            "{:?} is {} away from pi",
+            estimate,
+            error
+        );
+    }
+
+    #[test]
+    fn every_sampler_estimates_pi() {
+        for sampler in Sampler::ALL {
+            let args = args(&["--samples", "1000003", "--sampler", sampler.name()]);
+            let estimate = compute_pi_rayon(&args, 7, None, &quiet());
+
+            assert_eq!(estimate.samples, 1000003);
+            assert!((estimate.pi() - PI).abs() < 1e-2, "{:?}", sampler);
+        }
+    }
+
+    #[test]
+    fn lane_generator_estimates_pi() {
+        let args = args(&["--samples", "1000003", "--rng", "xoshiro"]);
+
+        assert_close(&compute_pi_rayon(&args, 9, None, &quiet()), 1000003);
+    }
+
+    #[test]
+    fn rayon_estimate_does_not_depend_on_threads() {
+        let samples = (2 * CHUNK + 17).to_string();
+        let args = args(&["--samples", &samples]);
+
+        let estimates: Vec<Estimate> = [1, 3]
+            .into_iter()
+            .map(|threads| {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap();
+                pool.install(|| compute_pi_rayon(&args, 1234, None, &quiet()))
+            })
+            .collect();
+
+        assert_eq!(estimates[0], estimates[1]);
+        assert_close(&estimates[0], 2 * CHUNK + 17);
+    }
+
+    #[test]
+    fn resumed_run_matches_uninterrupted_run() {
+        let path =
+            std::env::temp_dir().join(format!("compute-pi-test-{}.ckpt", std::process::id()));
+        let samples = (3 * CHUNK + 5).to_string();
+        let args = args(&[
+            "--samples",
+            &samples,
+            "--checkpoint",
+            path.to_str().unwrap(),
+            "--checkpoint-every",
+            &CHUNK.to_string(),
+        ]);
+        let uninterrupted = compute_pi_rayon(&args, 77, None, &quiet());
+
+        // what a run stopped after its first round would have saved
+        let run = Run::new(args.sampler, args.rng, 77);
+        let mut checkpoint = Checkpoint::new(77, args.samples, settings(&args));
+        checkpoint.inside = run.count_chunks(0..1, args.samples, &quiet());
+        checkpoint.chunks_done = 1;
+        checkpoint.save(&path).unwrap();
+
+        let resume_args = Args::parse_from([
+            "compute-pi",
+            "--quiet",
+            "--checkpoint",
+            path.to_str().unwrap(),
+            "--resume",
+        ]);
+        let resumed = load_checkpoint(&resume_args).unwrap();
+        assert_eq!(resumed, checkpoint);
+        assert_eq!(
+            compute_pi_rayon(&resume_args, 77, Some(resumed), &quiet()),
+            uninterrupted
+        );
+
+        // the resumed run leaves a finished checkpoint behind
+        let finished = Checkpoint::load(&path).unwrap();
+        assert_eq!(finished.chunks_done, 4);
+        assert_eq!(finished.inside, uninterrupted.inside);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn distributed_run_matches_local_run() {
+        let samples = (2 * CHUNK + 9).to_string();
+        let args = args(&["--samples", &samples]);
+        let local = compute_pi_rayon(&args, 21, None, &quiet());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let coordinator = Coordinator::new(
+            21,
+            args.samples,
+            settings(&args),
+            args.samples.div_ceil(CHUNK),
+            1,
+        );
+        let worker = std::thread::spawn(move || {
+            distributed::run_worker(addr, |task| count_task(task).unwrap()).unwrap()
+        });
+
+        let inside = coordinator.run(listener).unwrap();
+        assert_eq!(worker.join().unwrap(), 3);
+        assert_eq!(Estimate::new(inside, args.samples), local);
+    }
+
+    #[test]
+    fn progress_sees_every_sample() {
+        let samples = (CHUNK + 3).to_string();
+        let args = args(&["--samples", &samples]);
+
+        let progress = quiet();
+        let estimate = compute_pi_rayon(&args, 3, None, &progress);
+        assert_eq!(progress.estimate(), estimate);
+    }
+
+    #[test]
+    fn json_result_is_one_object() {
+        let args = args(&["--format", "json", "-m", "simd", "--samples", "1000"]);
+        let estimate = Estimate::new(785, 1000);
+        let line = json_result(&args, 8, &estimate, 0.5);
+
+        assert!(!line.contains('\n'));
+        assert!(line.starts_with("{\"method\":\"simd\",\"sampler\":\"uniform\""));
+        assert!(line.contains("\"seed\":8,\"samples\":1000,\"inside\":785,\"pi\":3.14,"));
+        assert!(line.contains("\"precision\":null,"));
+        assert!(line.ends_with("\"seconds\":0.5,\"samples_per_second\":2000.0}"));
+    }
+
+    #[test]
+    fn rejects_arguments_that_have_no_estimate() {
+        for bad in [
+            &["-n", "0"][..],
+            &["geometric", "-n", "0"],
+            &["geometric", "-n", "1"],
+            &["--confidence", "0"],
+            &["--confidence", "1"],
+            &["--confidence", "1.5"],
+            &["--precision", "0"],
+            &["--precision", "-1e-3"],
+            &["--precision", "inf"],
+        ] {
+            let parsed = Args::try_parse_from(["compute-pi"].iter().chain(bad));
+            assert!(parsed.is_err(), "{:?} was accepted", bad);
+        }
+
+        let args = args(&["--confidence", "0.5", "--precision", "1e-300"]);
+        assert_eq!((args.confidence, args.precision), (0.5, Some(1e-300)));
+    }
+
+    #[test]
+    fn converge_target_never_overflows() {
+        assert_eq!(next_target(0, 64, MAX_CHUNKS), 65);
+        assert_eq!(next_target(100 * CHUNK + 1, 64, MAX_CHUNKS), 101);
+        assert_eq!(next_target(u64::MAX, 64, MAX_CHUNKS), MAX_CHUNKS);
+        assert!(next_target(u64::MAX, MAX_CHUNKS - 1, MAX_CHUNKS)
+            .checked_mul(CHUNK)
+            .is_some());
+    }
+
+    #[test]
+    fn sobol_runs_stay_within_the_sequence() {
+        let too_many = ((1_u64 << 32) + 1).to_string();
+        assert!(check_samples(&args(&["--sampler", "sobol", "-n", "4294967296"])).is_ok());
+        assert!(check_samples(&args(&["--sampler", "sobol", "-n", &too_many])).is_err());
+        assert!(check_samples(&args(&[
+            "--sampler",
+            "sobol",
+            "-n",
+            &too_many,
+            "coordinate"
+        ]))
+        .is_err());
+        assert!(check_samples(&args(&[
+            "--sampler",
+            "sobol",
+            "-n",
+            &too_many,
+            "-m",
+            "simd"
+        ]))
+        .is_ok());
+        assert!(check_samples(&args(&["--sampler", "halton", "-n", &too_many])).is_ok());
+
+        assert_eq!(max_chunks(Sampler::Sobol) * CHUNK, 1 << 32);
+        assert_eq!(max_chunks(Sampler::Uniform), MAX_CHUNKS);
+    }
+
+    #[test]
+    fn digits_as_json() {
+        let line = json_digits(DigitsAlgorithm::Machin, "3.14159", 0.25, Some(5));
+        assert_eq!(
+            line,
+            "{\"algorithm\":\"machin\",\"digits\":5,\"pi\":\"3.14159\",\"seconds\":0.25,\"verified\":5}"
+        );
+
+        let line = json_hex_digits(3, "3F6A", 0.5, None);
+        assert_eq!(
+            line,
+            "{\"position\":3,\"count\":4,\"hex\":\"3F6A\",\"seconds\":0.5,\"verified\":null}"
+        );
+    }
+
+    #[test]
+    fn checkpoints_are_only_for_fixed_size_runs() {
+        let parsed =
+            Args::try_parse_from(["compute-pi", "--precision", "1e-3", "--checkpoint", "x"]);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn hex_positions_stay_in_range() {
+        let max = bbp::MAX_POSITION.to_string();
+        let past = (bbp::MAX_POSITION + 1).to_string();
+        assert!(Args::try_parse_from(["compute-pi", "hex", &max]).is_ok());
+        assert!(Args::try_parse_from(["compute-pi", "hex", &past]).is_err());
+        assert!(Args::try_parse_from(["compute-pi", "hex", "0"]).is_err());
+    }
+
+    #[test]
+    fn quiet_goes_anywhere() {
+        for line in [
+            &["compute-pi", "digits", "1000", "--quiet"][..],
+            &["compute-pi", "-q", "digits", "1000"],
+            &["compute-pi", "geometric", "-q"],
+            &["compute-pi", "--quiet"],
+        ] {
+            assert!(Args::parse_from(line).quiet, "{:?}", line);
+        }
+    }
 }
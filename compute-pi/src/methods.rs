@@ -0,0 +1,253 @@
+// The Monte Carlo counting methods behind the CLI, from the one point at a
+// time loop to the batched SIMD kernel on every rayon worker. Each takes a
+// sample count and a seed and feeds a `Progress` as it goes.
+
+use std::ops::Range;
+
+use clap::ValueEnum;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::{
+    kernel::Backend,
+    lanes::Xoshiro128PlusX8,
+    progress::Progress,
+    sampler::{stream_rng, Points, Sampler, UnitRng},
+    stats::Estimate,
+};
+
+// points generated per call into the counting kernel
+pub const BATCH: usize = 1024;
+
+// samples drawn from each RNG stream in the rayon method; the sample space is
+// cut into chunks of this size so the estimate for a seed doesn't depend on
+// how many threads share out the chunks
+pub const CHUNK: u64 = 1 << 22;
+
+// samples the serial methods draw between updates of the progress line
+const REPORT_EVERY: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Generator {
+    /// ChaCha8, one float at a time
+    Chacha,
+    /// eight xoshiro128+ lanes stepped together in vector registers
+    Xoshiro,
+}
+
+// everything that decides which points the chunks of a seeded run draw
+pub struct Run {
+    pub backend: Backend,
+    pub points: Points,
+    pub generator: Generator,
+    pub seed: u64,
+}
+
+impl Run {
+    // the random shift of the points comes from a stream no chunk uses
+    pub fn new(sampler: Sampler, generator: Generator, seed: u64) -> Self {
+        Self {
+            backend: Backend::detect(),
+            points: Points::new(sampler, &mut stream_rng(seed, u64::MAX)),
+            generator,
+            seed,
+        }
+    }
+
+    // counts the hits in `chunks`, stopping at sample `samples_end`, and adds
+    // each finished chunk to `progress`
+    pub fn count_chunks(&self, chunks: Range<u64>, samples_end: u64, progress: &Progress) -> u64 {
+        chunks
+            .into_par_iter()
+            .map(|chunk| {
+                let range = chunk * CHUNK..samples_end.min((chunk + 1) * CHUNK);
+                let samples = range.end - range.start;
+                let mut rng = stream_rng(self.seed, chunk);
+                let inside = match self.generator {
+                    Generator::Chacha => count_range(self.backend, &self.points, &mut rng, range),
+                    Generator::Xoshiro => {
+                        let mut lanes = Xoshiro128PlusX8::from_rng(&mut rng);
+                        count_range(self.backend, &self.points, &mut lanes, range)
+                    }
+                };
+
+                progress.add(samples, inside);
+                inside
+            })
+            .sum()
+    }
+}
+
+// counts the hits among the points with indices in `range`
+fn count_range(
+    backend: Backend,
+    points: &Points,
+    rng: &mut impl UnitRng,
+    range: Range<u64>,
+) -> u64 {
+    let mut arr_a: [f32; BATCH] = [0.0; BATCH];
+    let mut arr_b: [f32; BATCH] = [0.0; BATCH];
+
+    let mut count = 0;
+    for index in range.clone().step_by(BATCH) {
+        let len = BATCH.min((range.end - index) as usize);
+        let (xs, ys) = (&mut arr_a[..len], &mut arr_b[..len]);
+        points.fill(rng, index, xs, ys);
+
+        count += backend.count_inside(xs, ys) as u64;
+    }
+    count
+}
+
+// the batched kernel on every rayon worker, a chunk at a time
+pub fn compute_pi_rayon(
+    sampler: Sampler,
+    generator: Generator,
+    samples: u64,
+    seed: u64,
+    progress: &Progress,
+) -> Estimate {
+    let run = Run::new(sampler, generator, seed);
+    let inside = run.count_chunks(0..samples.div_ceil(CHUNK), samples, progress);
+    Estimate::new(inside, samples)
+}
+
+// adds what a serial method counted since the last report to `progress`,
+// once there is at least REPORT_EVERY samples of it
+fn report_progress(progress: &Progress, estimate: &Estimate, reported: &mut Estimate) {
+    if estimate.samples - reported.samples >= REPORT_EVERY {
+        progress.add(
+            estimate.samples - reported.samples,
+            estimate.inside - reported.inside,
+        );
+        *reported = *estimate;
+    }
+}
+
+// SIMD kernel, four points at a time on one thread
+pub fn compute_pi_simd(samples: u64, seed: u64, progress: &Progress) -> Estimate {
+    let mut estimate = Estimate::default();
+    let mut reported = Estimate::default();
+    let mut rng = stream_rng(seed, 0);
+    let mut arr_a: [f32; 4] = [0.0; 4];
+    let mut arr_b: [f32; 4] = [0.0; 4];
+    let backend = Backend::detect();
+
+    while estimate.samples < samples {
+        let len = 4.min(samples - estimate.samples) as usize;
+        rng.fill(&mut arr_a[..len]);
+        rng.fill(&mut arr_b[..len]);
+
+        let sum = backend.count_inside(&arr_a[..len], &arr_b[..len]);
+
+        assert!(sum <= 4);
+        estimate.inside += sum as u64;
+        estimate.samples += len as u64;
+
+        report_progress(progress, &estimate, &mut reported);
+    }
+
+    estimate
+}
+
+// fills large vectors of points, then counts them
+pub fn compute_pi(samples: u64, seed: u64, progress: &Progress) -> Estimate {
+    let mut estimate = Estimate::default();
+    let mut reported = Estimate::default();
+    let len = 33554432.min(samples) as usize;
+    let mut xs: Vec<f32> = vec![0.0; len];
+    let mut ys: Vec<f32> = vec![0.0; len];
+    let mut rng = stream_rng(seed, 0);
+
+    while estimate.samples < samples {
+        let len = xs.len().min((samples - estimate.samples) as usize);
+        for i in 0..len {
+            xs[i] = rng.gen();
+            ys[i] = rng.gen();
+        }
+
+        let mut inside = 0;
+        for i in 0..len {
+            if xs[i].powi(2) + ys[i].powi(2) <= 1.0 {
+                inside += 1;
+            }
+        }
+
+        estimate.inside += inside;
+        estimate.samples += len as u64;
+
+        report_progress(progress, &estimate, &mut reported);
+    }
+
+    estimate
+}
+
+// draws and tests one point at a time
+pub fn compute_pi_b(samples: u64, seed: u64, progress: &Progress) -> Estimate {
+    let mut estimate = Estimate::default();
+    let mut reported = Estimate::default();
+    let mut rng = stream_rng(seed, 0);
+
+    while estimate.samples < samples {
+        let loop_size = 50000000.min(samples - estimate.samples);
+        for _ in 0..loop_size {
+            let x: f32 = rng.gen();
+            let y: f32 = rng.gen();
+
+            if x.powi(2) + y.powi(2) <= 1.0 {
+                estimate.inside += 1;
+            }
+        }
+
+        estimate.samples += loop_size;
+
+        report_progress(progress, &estimate, &mut reported);
+    }
+
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn quiet() -> Progress {
+        Progress::new(0, false)
+    }
+
+    fn assert_close(estimate: &Estimate, samples: u64) {
+        assert_eq!(estimate.samples, samples);
+        let error = (estimate.pi() - PI).abs();
+        assert!(
+            error < 5.0 * estimate.std_error(),
+            "{:?} is {} away from pi",
+            estimate,
+            error
+        );
+    }
+
+    #[test]
+    fn every_method_estimates_pi() {
+        // not a multiple of any batch size, so every method ends on a short batch
+        let samples = 1000003;
+        let rayon = compute_pi_rayon(Sampler::Uniform, Generator::Chacha, samples, 42, &quiet());
+
+        assert_close(&rayon, samples);
+        assert_close(&compute_pi_simd(samples, 42, &quiet()), samples);
+        assert_close(&compute_pi(samples, 42, &quiet()), samples);
+        assert_close(&compute_pi_b(samples, 42, &quiet()), samples);
+    }
+
+    #[test]
+    fn same_seed_same_estimate() {
+        assert_eq!(
+            compute_pi_simd(100000, 5, &quiet()),
+            compute_pi_simd(100000, 5, &quiet())
+        );
+        assert_ne!(
+            compute_pi_simd(100000, 5, &quiet()),
+            compute_pi_simd(100000, 6, &quiet())
+        );
+    }
+}
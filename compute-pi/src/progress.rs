@@ -0,0 +1,197 @@
+// One progress line for a whole run. Workers add what they have counted to
+// shared counters as they finish each piece of work, and a single reporter
+// thread redraws the line on stderr with the rate, the time left and the
+// running estimate, so parallel workers never interleave their output.
+
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::stats::Estimate;
+
+// how often the line is redrawn on a terminal, and how often a new line is
+// written when stderr goes to a file
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(200);
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct Progress {
+    samples: AtomicU64,
+    inside: AtomicU64,
+    // samples counted by an earlier run, which don't count towards the rate
+    restored: AtomicU64,
+    total: AtomicU64,
+    start: Instant,
+    enabled: bool,
+    terminal: bool,
+}
+
+impl Progress {
+    // progress towards `total` samples; a disabled one only counts
+    pub fn new(total: u64, enabled: bool) -> Self {
+        Self {
+            samples: AtomicU64::new(0),
+            inside: AtomicU64::new(0),
+            restored: AtomicU64::new(0),
+            total: AtomicU64::new(total),
+            start: Instant::now(),
+            enabled,
+            terminal: io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn add(&self, samples: u64, inside: u64) {
+        self.inside.fetch_add(inside, Ordering::Relaxed);
+        self.samples.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    // counts that a resumed run starts from, without sampling them again
+    pub fn restore(&self, samples: u64, inside: u64) {
+        self.restored.fetch_add(samples, Ordering::Relaxed);
+        self.add(samples, inside);
+    }
+
+    // for runs that only learn how far they have to go as they sample
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    // everything counted so far
+    pub fn estimate(&self) -> Estimate {
+        Estimate::new(
+            self.inside.load(Ordering::Relaxed),
+            self.samples.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn line(&self) -> String {
+        let estimate = self.estimate();
+        let total = self.total.load(Ordering::Relaxed).max(estimate.samples);
+        let sampled = estimate.samples - self.restored.load(Ordering::Relaxed);
+        let rate = sampled as f64 / self.start.elapsed().as_secs_f64();
+
+        let mut line = format!(
+            "{}/{} samples ({:.1}%), {:.1} M samples/s",
+            estimate.samples,
+            total,
+            estimate.samples as f64 / total.max(1) as f64 * 100.0,
+            rate / 1e6
+        );
+        if rate > 0.0 {
+            let left = (total - estimate.samples) as f64 / rate;
+            line += &format!(", ETA {}", format_seconds(left));
+        }
+        if estimate.samples > 0 {
+            line += &format!(", pi ~ {:.6}", estimate.pi());
+        }
+        line
+    }
+
+    // prints a message on its own line, under the progress line
+    pub fn note(&self, msg: &str) {
+        if !self.enabled {
+            return;
+        }
+        if self.terminal {
+            eprint!("\r\x1b[K");
+        }
+        eprintln!("{}", msg);
+    }
+
+    // runs `f`, showing the progress line until it returns
+    pub fn show<T>(&self, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+
+        let finished = (Mutex::new(false), Condvar::new());
+        let interval = if self.terminal {
+            TERMINAL_INTERVAL
+        } else {
+            LOG_INTERVAL
+        };
+
+        let result = thread::scope(|scope| {
+            scope.spawn(|| loop {
+                let (lock, cvar) = &finished;
+                let done = cvar
+                    .wait_timeout_while(lock.lock().unwrap(), interval, |done| !*done)
+                    .unwrap()
+                    .0;
+                if *done {
+                    break;
+                }
+
+                if self.terminal {
+                    eprint!("\r\x1b[K{}", self.line());
+                    let _ = io::stderr().flush();
+                } else {
+                    eprintln!("{}", self.line());
+                }
+            });
+
+            let result = f();
+            *finished.0.lock().unwrap() = true;
+            finished.1.notify_all();
+            result
+        });
+
+        if self.terminal {
+            eprint!("\r\x1b[K");
+        }
+        result
+    }
+}
+
+// a duration as e.g. "45s", "3m05s" or "2h07m"
+fn format_seconds(seconds: f64) -> String {
+    let s = seconds.round() as u64;
+    match s {
+        0..=59 => format!("{}s", s),
+        60..=3599 => format!("{}m{:02}s", s / 60, s % 60),
+        _ => format!("{}h{:02}m", s / 3600, s / 60 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn counts_from_every_worker() {
+        let progress = Progress::new(1000, false);
+        (0..100)
+            .into_par_iter()
+            .for_each(|i| progress.add(10, i % 8));
+
+        assert_eq!(progress.estimate(), Estimate::new(342, 1000));
+        assert!(progress.line().starts_with("1000/1000 samples (100.0%)"));
+    }
+
+    #[test]
+    fn restored_samples_are_not_in_the_rate() {
+        let progress = Progress::new(1000, false);
+        progress.restore(600, 450);
+
+        assert_eq!(progress.estimate(), Estimate::new(450, 600));
+        assert!(progress.line().contains(" 0.0 M samples/s"));
+        assert!(!progress.line().contains("ETA"));
+
+        progress.add(100, 80);
+        assert_eq!(progress.estimate(), Estimate::new(530, 700));
+        assert!(progress.line().contains("ETA"));
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(format_seconds(4.4), "4s");
+        assert_eq!(format_seconds(185.0), "3m05s");
+        assert_eq!(format_seconds(7620.0), "2h07m");
+    }
+}
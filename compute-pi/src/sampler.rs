@@ -0,0 +1,255 @@
+// Ways of placing points in the unit square for the counting kernel. Points
+// are addressed by their index in the run, so a chunk of the sample space can
+// be filled by any worker and still land on the same points.
+
+use clap::ValueEnum;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::lanes::Xoshiro128PlusX8;
+
+// stream `stream` of the generator for `seed`. ChaCha streams don't overlap,
+// so every chunk gets its own independent sequence without jumping ahead
+pub fn stream_rng(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+// a generator that can fill a slice with uniform floats in [0, 1) at once
+pub trait UnitRng: Rng {
+    fn fill_unit(&mut self, out: &mut [f32]) {
+        self.fill(out);
+    }
+}
+
+impl UnitRng for ChaCha8Rng {}
+
+impl UnitRng for Xoshiro128PlusX8 {
+    fn fill_unit(&mut self, out: &mut [f32]) {
+        Xoshiro128PlusX8::fill_unit(self, out);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sampler {
+    /// independent uniform points
+    Uniform,
+    /// one jittered point per cell of a square grid over each batch
+    Stratified,
+    /// uniform points paired with their reflections (1 - x, 1 - y)
+    Antithetic,
+    /// Halton sequence in bases 2 and 3, randomly shifted
+    Halton,
+    /// first two Sobol dimensions, randomly digit-shifted
+    Sobol,
+}
+
+impl Sampler {
+    pub const ALL: [Sampler; 5] = [
+        Sampler::Uniform,
+        Sampler::Stratified,
+        Sampler::Antithetic,
+        Sampler::Halton,
+        Sampler::Sobol,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sampler::Uniform => "uniform",
+            Sampler::Stratified => "stratified",
+            Sampler::Antithetic => "antithetic",
+            Sampler::Halton => "halton",
+            Sampler::Sobol => "sobol",
+        }
+    }
+
+    // how many points a run can take before the sampler starts repeating
+    // them, if it ever does
+    pub fn period(&self) -> Option<u64> {
+        match self {
+            Sampler::Sobol => Some(1 << 32),
+            _ => None,
+        }
+    }
+}
+
+// a sampler with the random shift for one seed. The low-discrepancy
+// sequences are deterministic, so the shift is what makes runs with different
+// seeds independent estimates
+#[derive(Debug, Clone, Copy)]
+pub struct Points {
+    sampler: Sampler,
+    shift: [u32; 2],
+}
+
+impl Points {
+    pub fn new(sampler: Sampler, rng: &mut impl Rng) -> Self {
+        Self {
+            sampler,
+            shift: rng.gen(),
+        }
+    }
+
+    // fills xs and ys with the points starting at index `first`, drawing any
+    // randomness from `rng`
+    pub fn fill(&self, rng: &mut impl UnitRng, first: u64, xs: &mut [f32], ys: &mut [f32]) {
+        assert!(xs.len() == ys.len());
+        if let Some(period) = self.sampler.period() {
+            assert!(
+                first + xs.len() as u64 <= period,
+                "the {} sampler only has {} points",
+                self.sampler.name(),
+                period
+            );
+        }
+
+        match self.sampler {
+            Sampler::Uniform => {
+                rng.fill_unit(xs);
+                rng.fill_unit(ys);
+            }
+            Sampler::Stratified => fill_stratified(rng, xs, ys),
+            Sampler::Antithetic => {
+                let half = xs.len() / 2;
+                rng.fill_unit(&mut xs[..half]);
+                rng.fill_unit(&mut ys[..half]);
+                for i in 0..half {
+                    xs[half + i] = 1.0 - xs[i];
+                    ys[half + i] = 1.0 - ys[i];
+                }
+                // odd lengths get one unpaired point
+                if xs.len() % 2 == 1 {
+                    xs[xs.len() - 1] = rng.gen();
+                    ys[ys.len() - 1] = rng.gen();
+                }
+            }
+            Sampler::Halton => {
+                let shift = self.shift.map(unit_f64);
+                for (i, (x, y)) in xs.iter_mut().zip(ys.iter_mut()).enumerate() {
+                    let index = first + i as u64;
+                    *x = (radical_inverse(index, 2) + shift[0]).fract() as f32;
+                    *y = (radical_inverse(index, 3) + shift[1]).fract() as f32;
+                }
+            }
+            Sampler::Sobol => {
+                for (i, (x, y)) in xs.iter_mut().zip(ys.iter_mut()).enumerate() {
+                    // within the period, so the index fits in 32 bits
+                    let index = (first + i as u64) as u32;
+                    *x = unit_f32(index.reverse_bits() ^ self.shift[0]);
+                    *y = unit_f32(sobol_second_dim(index) ^ self.shift[1]);
+                }
+            }
+        }
+    }
+}
+
+// splits the square into a side x side grid and puts one uniform point in
+// each cell; points that don't fill a whole grid stay uniform
+fn fill_stratified(rng: &mut impl UnitRng, xs: &mut [f32], ys: &mut [f32]) {
+    let side = (xs.len() as f64).sqrt() as usize;
+    let cells = side * side;
+    let width = 1.0 / side as f32;
+
+    rng.fill_unit(xs);
+    rng.fill_unit(ys);
+    for (cell, (x, y)) in xs[..cells].iter_mut().zip(&mut ys[..cells]).enumerate() {
+        let (row, col) = (cell / side, cell % side);
+        *x = (col as f32 + *x) * width;
+        *y = (row as f32 + *y) * width;
+    }
+}
+
+// the digits of `index` in `base`, mirrored around the radix point
+fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut scale = inv_base;
+    let mut result = 0.0;
+
+    while index > 0 {
+        result += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inv_base;
+    }
+
+    result
+}
+
+// the second Sobol dimension, from primitive polynomial x + 1: direction
+// number j is v_j = v_{j-1} ^ (v_{j-1} >> 1), starting at 1/2
+fn sobol_second_dim(index: u32) -> u32 {
+    let mut direction = 1_u32 << 31;
+    let mut result = 0;
+    let mut bits = index;
+
+    while bits != 0 {
+        if bits & 1 == 1 {
+            result ^= direction;
+        }
+        direction ^= direction >> 1;
+        bits >>= 1;
+    }
+
+    result
+}
+
+// top 24 bits as a float in [0, 1), exactly representable in an f32
+fn unit_f32(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
+
+fn unit_f64(bits: u32) -> f64 {
+    bits as f64 / (1_u64 << 32) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverses() {
+        assert_eq!(radical_inverse(0, 2), 0.0);
+        assert_eq!(radical_inverse(1, 2), 0.5);
+        assert_eq!(radical_inverse(6, 2), 0.375);
+        assert!((radical_inverse(5, 3) - 7.0 / 9.0).abs() < 1e-15);
+        assert!((radical_inverse(1 << 40, 2) - 2f64.powi(-41)).abs() < 1e-27);
+    }
+
+    #[test]
+    fn sobol_second_dimension() {
+        let first: Vec<u32> = (0..8).map(sobol_second_dim).collect();
+        // 0, 1/2, 3/4, 1/4, 5/8, 1/8, 3/8, 7/8
+        let eighths: Vec<u32> = [0, 4, 6, 2, 5, 1, 3, 7].iter().map(|k| k << 29).collect();
+        assert_eq!(first, eighths);
+
+        // every 2^k consecutive points from 0 put one point in each 1/2^k
+        let mut cells: Vec<u32> = (0..1024).map(|i| sobol_second_dim(i) >> 22).collect();
+        cells.sort_unstable();
+        assert_eq!(cells, (0..1024).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn stratified_puts_one_point_per_cell() {
+        let mut rng = stream_rng(39, 0);
+        let (mut xs, mut ys) = (vec![0.0; 103], vec![0.0; 103]);
+        fill_stratified(&mut rng, &mut xs, &mut ys);
+
+        // a 10 x 10 grid, and three leftover uniform points
+        let mut cells: Vec<usize> = xs[..100]
+            .iter()
+            .zip(&ys[..100])
+            .map(|(x, y)| (y * 10.0) as usize * 10 + (x * 10.0) as usize)
+            .collect();
+        cells.sort_unstable();
+        assert_eq!(cells, (0..100).collect::<Vec<usize>>());
+        assert!(xs.iter().chain(&ys).all(|v| (0.0..1.0).contains(v)));
+    }
+
+    #[test]
+    #[should_panic(expected = "only has 4294967296 points")]
+    fn sobol_stops_at_its_period() {
+        let points = Points::new(Sampler::Sobol, &mut stream_rng(39, 0));
+        let (mut xs, mut ys) = (vec![0.0; 8], vec![0.0; 8]);
+        points.fill(&mut stream_rng(39, 1), (1 << 32) - 4, &mut xs, &mut ys);
+    }
+}
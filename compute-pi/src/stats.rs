@@ -0,0 +1,127 @@
+// Error estimates for hit-or-miss Monte Carlo. Each sample is a Bernoulli
+// trial that lands inside the quarter circle with probability p = pi / 4, so
+// the estimate 4 * inside / samples has standard error 4 * sqrt(p (1 - p) / n).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Estimate {
+    pub inside: u64,
+    pub samples: u64,
+}
+
+impl Estimate {
+    pub fn new(inside: u64, samples: u64) -> Self {
+        Self { inside, samples }
+    }
+
+    fn hit_rate(&self) -> f64 {
+        self.inside as f64 / self.samples as f64
+    }
+
+    pub fn pi(&self) -> f64 {
+        4.0 * self.hit_rate()
+    }
+
+    pub fn std_error(&self) -> f64 {
+        let p = self.hit_rate();
+        4.0 * (p * (1.0 - p) / self.samples as f64).sqrt()
+    }
+
+    // half the width of the two-sided confidence interval at `confidence`
+    pub fn half_width(&self, confidence: f64) -> f64 {
+        z_score(confidence) * self.std_error()
+    }
+
+    // total samples needed for the interval to shrink to +-`precision`,
+    // assuming the hit rate stays where it is
+    pub fn samples_for(&self, precision: f64, confidence: f64) -> u64 {
+        let p = self.hit_rate();
+        let sigma = 4.0 * (p * (1.0 - p)).sqrt();
+        ((z_score(confidence) * sigma / precision).powi(2)).ceil() as u64
+    }
+}
+
+// the z such that a standard normal lies in [-z, z] with probability
+// `confidence`
+pub fn z_score(confidence: f64) -> f64 {
+    assert!(confidence > 0.0 && confidence < 1.0);
+    inverse_normal_cdf(0.5 + confidence / 2.0)
+}
+
+// Acklam's rational approximation (relative error below 1.2e-9)
+fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z_scores() {
+        assert!((z_score(0.95) - 1.959964).abs() < 1e-6);
+        assert!((z_score(0.99) - 2.575829).abs() < 1e-6);
+        assert!((z_score(0.999999) - 4.891638).abs() < 1e-6);
+    }
+
+    #[test]
+    fn counts_past_u32() {
+        let estimate = Estimate::new(3 << 40, 4 << 40);
+
+        assert_eq!(estimate.pi(), 3.0);
+        assert!(estimate.std_error() > 0.0 && estimate.std_error() < 1e-5);
+    }
+
+    #[test]
+    fn samples_for_precision() {
+        let estimate = Estimate::new(785_398, 1_000_000);
+        let needed = estimate.samples_for(1e-3, 0.99);
+
+        // sqrt(p (1 - p)) * 4 ~= 1.642 standard deviations per sample
+        assert!((needed as f64 / 1.789e7 - 1.0).abs() < 1e-2, "{}", needed);
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// A problem found while compiling a school, with the line of source it points
+// at, so it can be shown without the scanner it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    // the characters from..=to of the file, all on `line`
+    pub from: u32,
+    pub to: u32,
+    pub msg: String,
+    pub hint: Option<String>,
+    // the whole source line
+    pub excerpt: String,
+}
+
+impl Diagnostic {
+    pub fn fmt_for_terminal(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        let first_level_indent: String = " ".repeat(4);
+        let excerpt_indent: String = " ".repeat(8);
+
+        let (label, colour) = match self.severity {
+            Severity::Error => ("Error", "31"),
+            Severity::Warning => ("Warning", "33"),
+        };
+
+        writeln!(
+            f,
+            "\x1b[1m\x1b[{}m{}\x1b[0m: \x1b[1m{}\x1b[0m",
+            colour, label, self.msg
+        )?;
+        writeln!(
+            f,
+            "{}in {}:{}:{}\n",
+            first_level_indent, self.file, self.line, self.col
+        )?;
+
+        writeln!(
+            f,
+            "{}\x1b[1m\x1b[34m{:>3}:\x1b[0m {}",
+            excerpt_indent, self.line, self.excerpt
+        )?;
+
+        writeln!(
+            f,
+            "{}{}\x1b[1m\x1b[{}m{}\x1b[0m\n",
+            excerpt_indent,
+            " ".repeat(self.col + 4),
+            colour,
+            "^".repeat((self.to.saturating_sub(self.from) + 1) as usize)
+        )?;
+        if let Some(hint) = &self.hint {
+            writeln!(f, "{}\x1b[1mhint:\x1b[0m {}", first_level_indent, hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.file, self.line, self.col, severity, self.msg
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, " (hint: {})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+// Everything reported for a school. Only the first error on a line is kept:
+// stages go on after an error by skipping the rest of its line, so anything
+// later stages find there is usually fallout from the same mistake.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        let line_has_error = self
+            .errors()
+            .any(|d| d.file == diagnostic.file && d.line == diagnostic.line);
+        if diagnostic.severity == Severity::Error && line_has_error {
+            return;
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.iter().filter(|d| d.severity == Severity::Warning)
+    }
+
+    // orders the diagnostics by position, keeping the files in the order
+    // they were first reported in
+    pub fn sort(&mut self) {
+        let mut files: Vec<String> = Vec::new();
+        for diagnostic in &self.diagnostics {
+            if !files.contains(&diagnostic.file) {
+                files.push(diagnostic.file.clone());
+            }
+        }
+
+        self.diagnostics.sort_by_key(|d| {
+            let file = files.iter().position(|f| *f == d.file);
+            (file, d.from, d.to)
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    // every diagnostic with colours and an excerpt of the source
+    pub fn fmt_for_terminal(&self) -> String {
+        let mut out = String::new();
+        for diagnostic in &self.diagnostics {
+            diagnostic.fmt_for_terminal(&mut out).unwrap();
+        }
+        out
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostics: vec![diagnostic],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.iter()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
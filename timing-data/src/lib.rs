@@ -0,0 +1,109 @@
+// Compiles a school's `school.txt` and `schedule.txt` into the `school.json`
+// and `schedule.json` the app reads. The sources go through the tokenizer,
+// the parser and the validator before being serialized; anything wrong with
+// them comes back as `Diagnostics` pointing into the source.
+
+pub mod diagnostics;
+mod parser;
+mod serialize;
+mod tokenizer;
+mod utils;
+mod validator;
+
+pub use diagnostics::{Diagnostic, Diagnostics, Severity};
+use utils::Scanner;
+use validator::SchoolInfo;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledSchool {
+    pub schedule: String,
+    pub school: String,
+    // nothing stopping the school from compiling, but worth a look
+    pub warnings: Diagnostics,
+}
+
+// compiles the two files' contents, calling them school.txt and schedule.txt
+// in diagnostics
+pub fn compile(school_src: &str, schedule_src: &str) -> Result<CompiledSchool, Diagnostics> {
+    compile_named("school.txt", school_src, "schedule.txt", schedule_src)
+}
+
+// compiles the two files' contents, naming them as given in diagnostics
+pub fn compile_named(
+    school_name: &str,
+    school_src: &str,
+    schedule_name: &str,
+    schedule_src: &str,
+) -> Result<CompiledSchool, Diagnostics> {
+    let school_scanner = Scanner::new(school_name, school_src);
+    let schedule_scanner = Scanner::new(schedule_name, schedule_src);
+
+    let mut diagnostics = Diagnostics::new();
+    let result = process_school(school_scanner, schedule_scanner, &mut diagnostics);
+    diagnostics.sort();
+
+    match result {
+        Some((schedule, school)) if !diagnostics.has_errors() => Ok(CompiledSchool {
+            schedule,
+            school,
+            warnings: diagnostics,
+        }),
+        _ => Err(diagnostics),
+    }
+}
+
+// each stage goes on past the errors it finds, so that one run reports as
+// many of them as it can. A stage only runs once the ones before it are
+// clean, as it would mostly report fallout from their errors otherwise
+fn process_school(
+    mut school_scanner: Scanner,
+    mut schedule_scanner: Scanner,
+    diagnostics: &mut Diagnostics,
+) -> Option<(String, String)> {
+    let school_tokens = tokenizer::make_tokens(&mut school_scanner, diagnostics);
+    let schedule_tokens = tokenizer::make_tokens(&mut schedule_scanner, diagnostics);
+
+    let school_ast = parser::gen(school_tokens, &school_scanner, diagnostics);
+    let schedule_ast = parser::gen(schedule_tokens, &schedule_scanner, diagnostics);
+
+    if diagnostics.has_errors() {
+        return None;
+    }
+
+    // make school info
+    let school_directives =
+        validator::school_to_school_info(school_ast, &school_scanner, diagnostics);
+    let schedule_directives =
+        validator::schedule_to_school_info(schedule_ast, &schedule_scanner, diagnostics);
+
+    let ((periods, non_periods, presets), (repeat, calendar)) =
+        (school_directives?, schedule_directives?);
+
+    let mut school_info = SchoolInfo {
+        repeat,
+        periods,
+        non_periods,
+        presets,
+        calendar,
+    };
+
+    validator::high_level_verifier(
+        &school_info,
+        &school_scanner,
+        &schedule_scanner,
+        diagnostics,
+    );
+    if diagnostics.has_errors() {
+        return None;
+    }
+
+    validator::prune(&mut school_info, &school_scanner, diagnostics);
+
+    // the JSON structs only hold strings, so serializing them can't fail
+    let schedule_json =
+        serialize::serialize_to_schedule(&school_info).expect("schedule serializes to JSON");
+    let school_json =
+        serialize::serialize_to_school(&school_info).expect("school serializes to JSON");
+
+    Some((schedule_json, school_json))
+}
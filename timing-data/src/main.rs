@@ -1,16 +1,8 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
-mod parser;
-mod serialize;
-mod tokenizer;
-mod utils;
-mod validator;
-
 use clap::Parser;
 use std::{collections::HashMap, fs, path::Path};
-use utils::Scanner;
-use validator::SchoolInfo;
 
 #[derive(Parser)]
 struct Args {
@@ -21,45 +13,8 @@ struct Args {
     dry_run: bool,
 }
 
-struct SchoolJsonFiles {
-    schedule: String,
-    school: String,
-}
-
-fn process_school(
-    mut schedule_scanner: Scanner,
-    mut school_scanner: Scanner,
-) -> Result<SchoolJsonFiles> {
-    let schedule_tokens = tokenizer::make_tokens(&mut schedule_scanner)?;
-    let school_tokens = tokenizer::make_tokens(&mut school_scanner)?;
-
-    let schedule_ast = parser::gen(schedule_tokens, &schedule_scanner)?;
-    let school_ast = parser::gen(school_tokens, &school_scanner)?;
-
-    // make school info
-    let (periods, non_periods, presets) =
-        validator::school_to_school_info(school_ast, &school_scanner)?;
-
-    let (repeat, calendar) = validator::schedule_to_school_info(schedule_ast, &schedule_scanner)?;
-
-    let mut school_info = SchoolInfo {
-        repeat,
-        periods,
-        non_periods,
-        presets,
-        calendar,
-    };
-
-    validator::high_level_verifier(&school_info, &school_scanner, &schedule_scanner)?;
-    validator::prune(&mut school_info);
-
-    let schedule_json = serialize::serialize_to_schedule(&school_info)?;
-    let school_json = serialize::serialize_to_school(&school_info)?;
-
-    Ok(SchoolJsonFiles {
-        schedule: schedule_json,
-        school: school_json,
-    })
+fn read_source(filename: &str) -> Result<String> {
+    fs::read_to_string(filename).map_err(|_| anyhow!("Could not open file {}", filename))
 }
 
 fn load_schools_from_directory(args: Args) -> Result<()> {
@@ -86,21 +41,27 @@ fn load_schools_from_directory(args: Args) -> Result<()> {
             None => entry,
         };
 
-        let schedule_scanner = Scanner::new(&format!("data/{}/schedule.txt", folder))?;
-        let school_scanner = Scanner::new(&format!("data/{}/school.txt", folder))?;
+        let schedule_file = format!("data/{}/schedule.txt", folder);
+        let school_file = format!("data/{}/school.txt", folder);
+        let schedule_src = read_source(&schedule_file)?;
+        let school_src = read_source(&school_file)?;
 
-        let result = process_school(schedule_scanner, school_scanner);
+        let result =
+            timing_data::compile_named(&school_file, &school_src, &schedule_file, &schedule_src);
 
         match result {
             Ok(files) => {
+                if !files.warnings.is_empty() {
+                    println!("{}", files.warnings.fmt_for_terminal());
+                }
                 if !args.dry_run {
                     fs::create_dir(out_path.join(entry))?;
                     fs::write(out_path.join(entry).join("schedule.json"), files.schedule)?;
                     fs::write(out_path.join(entry).join("school.json"), files.school)?;
                 }
             }
-            Err(e) => {
-                println!("{}", e);
+            Err(diagnostics) => {
+                println!("{}", diagnostics.fmt_for_terminal());
             }
         }
     }
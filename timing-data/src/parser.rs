@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 
+use crate::diagnostics::Diagnostics;
 use crate::utils::CompilerError;
-use anyhow::Result;
 
 use crate::tokenizer::{Literal, Token};
 
@@ -73,156 +73,201 @@ fn err_from_tok(tok: &Token, msg: &str, hint: &str) -> CompilerError {
 }
 
 fn next_tok(tokens: &mut VecDeque<Token>) -> Result<Token, CompilerError> {
-    if tokens.len() == 0 {
-        return Err(CompilerError::new(
-            u32::MAX,
-            u32::MAX,
-            "unexpected end of file",
-            "",
-        ));
-    }
-    Ok(tokens.pop_front().unwrap())
+    tokens
+        .pop_front()
+        .ok_or_else(|| CompilerError::new(u32::MAX, u32::MAX, "unexpected end of file", ""))
 }
 
-fn expect_newline_after_directive_header(
+// takes the next token, checking it with `expected`. A newline that isn't
+// expected stays where it is, so that after the error parsing can go on from
+// the end of the line
+fn expect_tok(
     tokens: &mut VecDeque<Token>,
-) -> Result<(), CompilerError> {
+    expected: fn(&Literal) -> bool,
+    msg: &str,
+    hint: &str,
+) -> Result<Token, CompilerError> {
     let tok = next_tok(tokens)?;
-    if !matches!(tok.literal, Literal::NewLine) {
-        return Err(err_from_tok(
-            &tok,
-            "expected newline after directive header",
-            "",
-        ));
+    if expected(&tok.literal) {
+        return Ok(tok);
     }
-    Ok(())
-}
 
-fn parse_repeat(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, CompilerError> {
-    // expect date
-    let date = next_tok(tokens)?;
-
-    if !matches!(date.literal, Literal::Date(_)) {
-        return Err(err_from_tok(
-            &date,
-            "expected date after repeat directive",
-            "this represents the date to start the repeat pattern",
-        ));
+    let err = err_from_tok(&tok, msg, hint);
+    if matches!(tok.literal, Literal::NewLine) {
+        tokens.push_front(tok);
     }
+    Err(err)
+}
 
-    expect_newline_after_directive_header(tokens)?;
-
-    let mut pattern = Vec::new();
-
+// after an error in a line, drops the rest of it
+fn skip_line(tokens: &mut VecDeque<Token>) {
     while let Some(tok) = tokens.front() {
-        match tok.literal {
-            Literal::NewLine => {
-                tokens.pop_front();
-            }
-            Literal::Identifier(_) => {
-                pattern.push(tokens.pop_front().unwrap());
-            }
-            _ => break,
+        if matches!(tok.literal, Literal::NewLine) {
+            break;
         }
+        tokens.pop_front();
     }
-
-    Ok(DirectiveItem::Repeat(Repeat { date, pattern }))
 }
 
-fn parse_periods(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, CompilerError> {
-    expect_newline_after_directive_header(tokens)?;
-
-    let mut periods = Vec::new();
-
+// after an error in a directive's header, drops the whole directive
+fn skip_directive(tokens: &mut VecDeque<Token>) {
     while let Some(tok) = tokens.front() {
-        match tok.literal {
-            Literal::NewLine => {
-                tokens.pop_front();
-            }
-            Literal::PeriodName(_) => {
-                periods.push(tokens.pop_front().unwrap());
-            }
-            _ => break,
+        if matches!(tok.literal, Literal::Asterisk) {
+            break;
         }
+        tokens.pop_front();
     }
-
-    Ok(DirectiveItem::Periods(periods))
 }
 
-fn parse_nonperiods(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, CompilerError> {
-    expect_newline_after_directive_header(tokens)?;
+fn unexpected_tok(tok: &Token) -> CompilerError {
+    err_from_tok(
+        tok,
+        "unexpected token",
+        "ensure this token is part of some directive, which start with \"*\"",
+    )
+}
 
-    let mut periods = Vec::new();
+// the list of a directive runs to the next directive. `parse_item` reads one
+// line of it, starting at a token that isn't a newline; a line it can't make
+// sense of is reported and skipped
+fn parse_items<T>(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+    mut parse_item: impl FnMut(&mut VecDeque<Token>) -> Result<T, CompilerError>,
+) -> Vec<T> {
+    let mut items = Vec::new();
 
     while let Some(tok) = tokens.front() {
         match tok.literal {
+            Literal::Asterisk => break,
             Literal::NewLine => {
                 tokens.pop_front();
             }
-            Literal::PeriodName(_) => {
-                periods.push(tokens.pop_front().unwrap());
-            }
-            _ => break,
+            _ => match parse_item(tokens) {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    errors.push(e);
+                    skip_line(tokens);
+                }
+            },
         }
     }
 
-    Ok(DirectiveItem::NonPeriods(periods))
+    items
 }
 
-fn parse_schedule(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, CompilerError> {
-    // expect identifier
-    let ident = next_tok(tokens)?;
+// a token of the kind `expected`, alone on its line
+fn parse_list_item(
+    tokens: &mut VecDeque<Token>,
+    expected: fn(&Literal) -> bool,
+) -> Result<Token, CompilerError> {
+    let tok = tokens.pop_front().unwrap();
+    if !expected(&tok.literal) {
+        return Err(unexpected_tok(&tok));
+    }
+    Ok(tok)
+}
 
-    if !matches!(ident.literal, Literal::Identifier(_)) {
-        return Err(err_from_tok(
-            &ident,
-            "expected schedule id after schedule directive",
-            "a schedule identifier must be a-z, -, and 0-9",
-        ));
+fn expect_newline_after_directive_header(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) {
+    let newline = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::NewLine),
+        "expected newline after directive header",
+        "",
+    );
+
+    // the header itself was fine, so carry on with the directive's list
+    if let Err(e) = newline {
+        errors.push(e);
+        skip_line(tokens);
     }
+}
 
-    // expect name
-    let name = next_tok(tokens)?;
+fn parse_repeat(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<DirectiveItem, CompilerError> {
+    let date = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::Date(_)),
+        "expected date after repeat directive",
+        "this represents the date to start the repeat pattern",
+    )?;
 
-    if !matches!(name.literal, Literal::String(_)) {
-        return Err(err_from_tok(
-            &name,
-            "expected human-readable name after schedule id",
-            "please enclose your string in quotes — ex: \"Schedule A\"",
-        ));
-    }
+    expect_newline_after_directive_header(tokens, errors);
 
-    // expect newline
-    expect_newline_after_directive_header(tokens)?;
+    let pattern = parse_items(tokens, errors, |tokens| {
+        parse_list_item(tokens, |l| matches!(l, Literal::Identifier(_)))
+    });
 
-    // read events
-    let mut event_list = Vec::new();
+    Ok(DirectiveItem::Repeat(Repeat { date, pattern }))
+}
 
-    while let Some(tok) = tokens.front() {
-        match tok.literal {
-            Literal::NewLine => {
-                tokens.pop_front();
-            }
-            Literal::Time(_) => {
-                let time_tok = tokens.pop_front().unwrap();
-                let period_name_tok = next_tok(tokens)?;
-
-                if !matches!(period_name_tok.literal, Literal::PeriodName(_)) {
-                    return Err(err_from_tok(
-                        &period_name_tok,
-                        "expected period name after time",
-                        "",
-                    ));
-                }
+fn parse_periods(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<DirectiveItem, CompilerError> {
+    expect_newline_after_directive_header(tokens, errors);
 
-                event_list.push(Event {
-                    from: time_tok,
-                    name: period_name_tok,
-                });
-            }
-            _ => break,
-        }
-    }
+    let periods = parse_items(tokens, errors, |tokens| {
+        parse_list_item(tokens, |l| matches!(l, Literal::PeriodName(_)))
+    });
+
+    Ok(DirectiveItem::Periods(periods))
+}
+
+fn parse_nonperiods(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<DirectiveItem, CompilerError> {
+    expect_newline_after_directive_header(tokens, errors);
+
+    let periods = parse_items(tokens, errors, |tokens| {
+        parse_list_item(tokens, |l| matches!(l, Literal::PeriodName(_)))
+    });
+
+    Ok(DirectiveItem::NonPeriods(periods))
+}
+
+fn parse_event(tokens: &mut VecDeque<Token>) -> Result<Event, CompilerError> {
+    let time_tok = parse_list_item(tokens, |l| matches!(l, Literal::Time(_)))?;
+    let period_name_tok = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::PeriodName(_)),
+        "expected period name after time",
+        "",
+    )?;
+
+    Ok(Event {
+        from: time_tok,
+        name: period_name_tok,
+    })
+}
+
+fn parse_schedule(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<DirectiveItem, CompilerError> {
+    let ident = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::Identifier(_)),
+        "expected schedule id after schedule directive",
+        "a schedule identifier must be a-z, -, and 0-9",
+    )?;
+
+    let name = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::String(_)),
+        "expected human-readable name after schedule id",
+        "please enclose your string in quotes — ex: \"Schedule A\"",
+    )?;
+
+    expect_newline_after_directive_header(tokens, errors);
+
+    let event_list = parse_items(tokens, errors, parse_event);
 
     Ok(DirectiveItem::Preset(Preset {
         name,
@@ -231,125 +276,118 @@ fn parse_schedule(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, Compile
     }))
 }
 
-fn parse_calendar(tokens: &mut VecDeque<Token>) -> Result<DirectiveItem, CompilerError> {
-    expect_newline_after_directive_header(tokens)?;
-
-    let mut calendar_items = Vec::new();
-
-    while let Some(tok) = tokens.front() {
-        match tok.literal {
-            Literal::Date(_) => {
-                let from = tokens.pop_front().unwrap();
-
-                // is range?
-                let tok = next_tok(tokens)?;
-
-                let dates;
+fn parse_calendar_item(tokens: &mut VecDeque<Token>) -> Result<CalendarItem, CompilerError> {
+    let from = parse_list_item(tokens, |l| matches!(l, Literal::Date(_)))?;
+
+    // is range?
+    let dates = if matches!(tokens.front().map(|t| &t.literal), Some(Literal::Hyphen)) {
+        tokens.pop_front();
+        let to = expect_tok(
+            tokens,
+            |l| matches!(l, Literal::Date(_)),
+            "expected another date after hyphen",
+            "looking for date range expression of form {date}-{date}",
+        )?;
+
+        Date::Range(from, to)
+    } else {
+        Date::Date(from)
+    };
+
+    // parse schedule id
+    let preset = expect_tok(
+        tokens,
+        |l| matches!(l, Literal::Identifier(_)),
+        "expected schedule id (preset) after date",
+        "",
+    )?;
+
+    // custom name?
+    let name_override = if matches!(tokens.front().map(|t| &t.literal), Some(Literal::String(_))) {
+        tokens.pop_front()
+    } else {
+        None
+    };
+
+    Ok(CalendarItem {
+        dates,
+        name_override,
+        preset,
+    })
+}
 
-                if matches!(tok.literal, Literal::Hyphen) {
-                    let to = next_tok(tokens)?;
+fn parse_calendar(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<DirectiveItem, CompilerError> {
+    expect_newline_after_directive_header(tokens, errors);
 
-                    if !matches!(to.literal, Literal::Date(_)) {
-                        return Err(err_from_tok(
-                            &to,
-                            "expected another date after hyphen",
-                            "looking for date range expression of form {date}-{date}",
-                        ));
-                    }
+    let calendar_items = parse_items(tokens, errors, parse_calendar_item);
 
-                    dates = Date::Range(from, to);
-                } else {
-                    dates = Date::Date(from);
-                }
+    Ok(DirectiveItem::Calendar(calendar_items))
+}
 
-                // parse schedule id
-                let preset = match dates {
-                    Date::Range(_, _) => next_tok(tokens)?,
-                    Date::Date(_) => tok,
-                };
-
-                if !matches!(preset.literal, Literal::Identifier(_)) {
-                    return Err(err_from_tok(
-                        &preset,
-                        "expected schedule id (preset) after date",
-                        "",
-                    ));
-                }
+fn parse_directive(
+    tokens: &mut VecDeque<Token>,
+    errors: &mut Vec<CompilerError>,
+) -> Result<Directive, CompilerError> {
+    let asterisk = tokens.pop_front().unwrap();
+    if !matches!(asterisk.literal, Literal::Asterisk) {
+        return Err(unexpected_tok(&asterisk));
+    }
 
-                // custom name?
-                // can assume file ends in newline, so more tokens
-                let name_override = if matches!(tokens.front().unwrap().literal, Literal::String(_))
-                {
-                    Some(tokens.pop_front().unwrap())
-                } else {
-                    None
-                };
-
-                calendar_items.push(CalendarItem {
-                    dates,
-                    name_override,
-                    preset,
-                });
-            }
-            Literal::NewLine => {
-                tokens.pop_front();
-            }
-            _ => break,
+    let tok = next_tok(tokens)?;
+    let content = match tok.literal {
+        Literal::Repeat => parse_repeat(tokens, errors)?,
+        Literal::Calendar => parse_calendar(tokens, errors)?,
+        Literal::Periods => parse_periods(tokens, errors)?,
+        Literal::NonPeriods => parse_nonperiods(tokens, errors)?,
+        Literal::Schedule => parse_schedule(tokens, errors)?,
+        _ => {
+            return Err(err_from_tok(
+                &tok,
+                "invalid directive name",
+                "directives are denoted by an asterisk followed by the directive name (periods, non-periods, schedule, repeat, calendar)",
+            ));
         }
-    }
+    };
 
-    Ok(DirectiveItem::Calendar(calendar_items))
+    Ok(Directive {
+        content,
+        begin_tok: asterisk,
+    })
 }
 
-fn ast_gen(tokens: Vec<Token>) -> Result<Vec<Directive>, CompilerError> {
+fn ast_gen(tokens: Vec<Token>) -> (Vec<Directive>, Vec<CompilerError>) {
     let mut directives = Vec::new();
+    let mut errors = Vec::new();
     let mut tokens = VecDeque::from(tokens);
 
     skip_newlines(&mut tokens);
 
-    while tokens.len() > 0 {
-        let asterisk = tokens.pop_front().unwrap();
-        if !matches!(asterisk.literal, Literal::Asterisk) {
-            return Err(err_from_tok(
-                &asterisk,
-                "unexpected token",
-                "ensure this token is part of some directive, which start with \"*\"",
-            ));
-        }
-
-        let tok = next_tok(&mut tokens)?;
-        let content = match tok.literal {
-            Literal::Repeat => parse_repeat(&mut tokens)?,
-            Literal::Calendar => parse_calendar(&mut tokens)?,
-            Literal::Periods => parse_periods(&mut tokens)?,
-            Literal::NonPeriods => parse_nonperiods(&mut tokens)?,
-            Literal::Schedule => parse_schedule(&mut tokens)?,
-            _ => {
-                return Err(err_from_tok(
-                    &tok,
-                    "invalid directive name",
-                    "directives are denoted by an asterisk followed by the directive name (periods, non-periods, schedule, repeat, calendar)",
-                ));
+    while !tokens.is_empty() {
+        match parse_directive(&mut tokens, &mut errors) {
+            Ok(directive) => directives.push(directive),
+            Err(e) => {
+                errors.push(e);
+                skip_directive(&mut tokens);
             }
-        };
-
-        directives.push(Directive {
-            content,
-            begin_tok: asterisk,
-        });
+        }
 
         skip_newlines(&mut tokens);
     }
 
-    Ok(directives)
+    (directives, errors)
 }
 
-pub fn gen(tokens: Vec<Token>, scanner: &crate::utils::Scanner) -> Result<Vec<Directive>> {
-    let ast = ast_gen(tokens);
-
-    if let Err(e) = ast {
-        return Err(e.get_err_str(scanner));
+pub fn gen(
+    tokens: Vec<Token>,
+    scanner: &crate::utils::Scanner,
+    diagnostics: &mut Diagnostics,
+) -> Vec<Directive> {
+    let (ast, errors) = ast_gen(tokens);
+    for e in errors {
+        diagnostics.push(e.to_diagnostic(scanner));
     }
-
-    Ok(ast?)
+    ast
 }
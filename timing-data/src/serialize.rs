@@ -120,7 +120,7 @@ pub fn serialize_to_schedule(school_info: &SchoolInfo) -> Result<String, serde_j
                 Literal::String(n) => Some(n.to_string()),
                 _ => unreachable!(),
             },
-            None => None
+            None => None,
         };
 
         let mut calendar_item = CalendarItem {
@@ -1,6 +1,6 @@
+use crate::diagnostics::Diagnostics;
 use crate::utils::CompilerError;
 use crate::utils::Scanner;
-use anyhow::{anyhow, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -18,7 +18,7 @@ pub struct Date {
 }
 
 impl Date {
-    pub fn to_american(&self) -> String {
+    pub fn to_american(self) -> String {
         // format in m/d/y
         format!("{}/{}/{}", self.month, self.day, self.year)
     }
@@ -169,7 +169,7 @@ fn read_periods(scanner: &mut Scanner, tokens: &mut Vec<Token>) {
         scanner.read_char();
         if period_char == '\n' || period_char == '#' {
             let lexeme_trimmed = lexeme.trim_end();
-            if lexeme.len() > 0 {
+            if !lexeme.is_empty() {
                 tokens.push(Token {
                     lexeme: lexeme_trimmed.to_string(),
                     literal: Literal::PeriodName(lexeme_trimmed.to_string()),
@@ -194,94 +194,119 @@ fn read_periods(scanner: &mut Scanner, tokens: &mut Vec<Token>) {
     }
 }
 
-fn tokenizer(scanner: &mut Scanner) -> Result<Vec<Token>, CompilerError> {
+// after an error, skips the rest of the line so tokenizing picks up again on
+// the next one
+fn skip_line(scanner: &mut Scanner, tokens: &mut Vec<Token>) {
+    // the error may have been at the newline itself
+    if scanner.get_at(scanner.get_pos() as usize) == Some('\n') {
+        tokens.push(make_newline_token(scanner.get_pos()));
+        return;
+    }
+
+    while let Some(c) = scanner.peek_char() {
+        if c == '\n' {
+            break;
+        }
+        scanner.read_char();
+    }
+}
+
+fn tokenizer(scanner: &mut Scanner) -> (Vec<Token>, Vec<CompilerError>) {
     let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    let identifier_regex = Regex::new(r#"^[a-z]+([a-z0-9]+[-])*[a-z0-9]+$"#).unwrap();
 
     while let Some(c) = scanner.read_char() {
-        match c {
-            '*' => {
-                tokens.push(Token {
-                    lexeme: "*".to_string(),
-                    literal: Literal::Asterisk,
-                    byte_idx: scanner.get_pos(),
-                });
-            }
-            '\n' => {
-                tokens.push(make_newline_token(scanner.get_pos()));
+        if let Err(e) = read_token(c, scanner, &mut tokens, &identifier_regex) {
+            errors.push(e);
+            skip_line(scanner, &mut tokens);
+        }
+    }
+
+    (tokens, errors)
+}
+
+// reads the token starting with `c`, which has just been read
+fn read_token(
+    c: char,
+    scanner: &mut Scanner,
+    tokens: &mut Vec<Token>,
+    identifier_regex: &Regex,
+) -> Result<(), CompilerError> {
+    match c {
+        '*' => {
+            tokens.push(Token {
+                lexeme: "*".to_string(),
+                literal: Literal::Asterisk,
+                byte_idx: scanner.get_pos(),
+            });
+        }
+        '\n' => {
+            tokens.push(make_newline_token(scanner.get_pos()));
+        }
+        '"' => {
+            // beginning of a string
+            let byte_pos = scanner.get_pos();
+            let mut lexeme = String::new();
+            lexeme.push(c);
+
+            while let Some(string_char) = scanner.read_char() {
+                lexeme.push(string_char);
+                if string_char == '"' {
+                    break;
+                } else if string_char == '\n' {
+                    return Err(CompilerError::new(
+                        byte_pos,
+                        scanner.get_pos() - 1,
+                        "unterminated string",
+                        "strings must be enclosed in double quotes",
+                    ));
+                }
             }
-            '"' => {
-                // beginning of a string
-                let byte_pos = scanner.get_pos();
-                let mut lexeme = String::new();
-                lexeme.push(c);
-
-                while let Some(string_char) = scanner.read_char() {
-                    lexeme.push(string_char);
-                    if string_char == '"' {
-                        break;
-                    } else if string_char == '\n' {
-                        return Err(CompilerError::new(
-                            byte_pos,
-                            scanner.get_pos() - 1,
-                            "unterminated string",
-                            "strings must be enclosed in double quotes",
-                        ));
-                    }
+            tokens.push(make_string_token(lexeme, byte_pos)?);
+        }
+        '#' => {
+            // comment
+            while let Some(comment_char) = scanner.peek_char() {
+                if comment_char == '\n' {
+                    break;
                 }
-                tokens.push(make_string_token(lexeme, byte_pos)?);
+                scanner.read_char();
             }
-            '#' => {
-                // comment
-                while let Some(comment_char) = scanner.peek_char() {
-                    if comment_char == '\n' {
-                        break;
-                    }
+        }
+        '-' => {
+            // hyphen
+            tokens.push(Token {
+                lexeme: "-".to_string(),
+                literal: Literal::Hyphen,
+                byte_idx: scanner.get_pos(),
+            });
+        }
+        '0'..='9' => {
+            // date or time
+            let mut lexeme = String::new();
+            lexeme.push(c);
+            let byte_idx = scanner.get_pos();
+            let mut is_time = false;
+
+            while let Some(date_char) = scanner.peek_char() {
+                let date_uni = date_char as u8;
+                let valid = date_uni.is_ascii_digit() || date_uni == b':' || date_uni == b'/';
+                if !valid {
+                    break;
+                } else {
+                    is_time = is_time || date_char == ':';
+                    lexeme.push(date_char);
                     scanner.read_char();
                 }
             }
-            '-' => {
-                // hyphen
-                tokens.push(Token {
-                    lexeme: "-".to_string(),
-                    literal: Literal::Hyphen,
-                    byte_idx: scanner.get_pos(),
-                });
-            }
-            '0'..='9' => {
-                // date or time
-                let mut lexeme = String::new();
-                lexeme.push(c);
-                let byte_idx = scanner.get_pos();
-                let mut is_time = false;
-
-                while let Some(date_char) = scanner.peek_char() {
-                    let date_uni = date_char as u8;
-                    let valid = (date_uni >= b'0' && date_uni <= b'9')
-                        || date_uni == b':'
-                        || date_uni == b'/';
-                    if !valid {
-                        break;
-                    } else {
-                        is_time = is_time || date_char == ':';
-                        lexeme.push(date_char);
-                        scanner.read_char();
-                    }
-                }
 
-                if is_time {
-                    tokens.push(make_time_token(lexeme, byte_idx)?);
+            if is_time {
+                tokens.push(make_time_token(lexeme, byte_idx)?);
 
-                    // assert space after time
-                    if let Some(space_char) = scanner.read_char() {
-                        if space_char != ' ' {
-                            return Err(CompilerError::new(
-                                scanner.get_pos(),
-                                scanner.get_pos(),
-                                "expected space after time",
-                                "schedule items are of form {time} {period name}\\n",
-                            ));
-                        }
-                    } else {
+                // assert space after time
+                if let Some(space_char) = scanner.read_char() {
+                    if space_char != ' ' {
                         return Err(CompilerError::new(
                             scanner.get_pos(),
                             scanner.get_pos(),
@@ -289,161 +314,168 @@ fn tokenizer(scanner: &mut Scanner) -> Result<Vec<Token>, CompilerError> {
                             "schedule items are of form {time} {period name}\\n",
                         ));
                     }
-
-                    // parse PeriodName after time
-                    let byte_idx = scanner.get_pos();
-                    let mut pn_lexeme = String::new();
-                    while let Some(pn_char) = scanner.peek_char() {
-                        if pn_char == '\n' || pn_char == '#' {
-                            break;
-                        } else {
-                            pn_lexeme.push(pn_char);
-                            scanner.read_char();
-                        }
-                    }
-
-                    let pn_lexeme = pn_lexeme.trim_end();
-
-                    tokens.push(Token {
-                        lexeme: pn_lexeme.to_string(),
-                        literal: Literal::PeriodName(pn_lexeme.to_string()),
-                        byte_idx: byte_idx + 1,
-                    });
                 } else {
-                    tokens.push(make_date_token(lexeme, byte_idx)?);
+                    return Err(CompilerError::new(
+                        scanner.get_pos(),
+                        scanner.get_pos(),
+                        "expected space after time",
+                        "schedule items are of form {time} {period name}\\n",
+                    ));
                 }
-            }
-            'a'..='z' => {
-                // identifier
-                let byte_idx = scanner.get_pos();
-                let mut lexeme = String::new();
-                lexeme.push(c);
 
-                while let Some(identifier_char) = scanner.peek_char() {
-                    if identifier_char == ' ' || identifier_char == '\n' {
+                // parse PeriodName after time
+                let byte_idx = scanner.get_pos();
+                let mut pn_lexeme = String::new();
+                while let Some(pn_char) = scanner.peek_char() {
+                    if pn_char == '\n' || pn_char == '#' {
                         break;
                     } else {
-                        lexeme.push(identifier_char);
+                        pn_lexeme.push(pn_char);
                         scanner.read_char();
                     }
                 }
 
-                match lexeme.as_str() {
-                    "repeat" => {
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::Repeat,
-                            byte_idx,
-                        });
-                    }
-                    "calendar" => {
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::Calendar,
-                            byte_idx,
-                        });
-                    }
-                    "periods" => {
-                        match &tokens[tokens.len() - 1].literal {
-                            Literal::Asterisk => {}
-                            _ => {
-                                return Err(CompilerError::new(
-                                    scanner.get_pos() - lexeme.len() as u32 + 1,
-                                    scanner.get_pos(),
-                                    "periods must be preceded by an asterisk",
-                                    "directives are of form * {directive}",
-                                ));
-                            }
-                        }
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::Periods,
-                            byte_idx,
-                        });
+                let pn_lexeme = pn_lexeme.trim_end();
 
-                        // read until next directive (*)
-                        read_periods(scanner, &mut tokens);
-                    }
-                    "non-periods" => {
-                        if !matches!(tokens[tokens.len() - 1].literal, Literal::Asterisk) {
-                            return Err(CompilerError::new(
+                tokens.push(Token {
+                    lexeme: pn_lexeme.to_string(),
+                    literal: Literal::PeriodName(pn_lexeme.to_string()),
+                    byte_idx: byte_idx + 1,
+                });
+            } else {
+                tokens.push(make_date_token(lexeme, byte_idx)?);
+            }
+        }
+        'a'..='z' => {
+            // identifier
+            let byte_idx = scanner.get_pos();
+            let mut lexeme = String::new();
+            lexeme.push(c);
+
+            while let Some(identifier_char) = scanner.peek_char() {
+                if identifier_char == ' ' || identifier_char == '\n' {
+                    break;
+                } else {
+                    lexeme.push(identifier_char);
+                    scanner.read_char();
+                }
+            }
+
+            match lexeme.as_str() {
+                "repeat" => {
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::Repeat,
+                        byte_idx,
+                    });
+                }
+                "calendar" => {
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::Calendar,
+                        byte_idx,
+                    });
+                }
+                "periods" => {
+                    match tokens.last().map(|t| &t.literal) {
+                        Some(Literal::Asterisk) => {}
+                        _ => {
+                            let err = CompilerError::new(
                                 scanner.get_pos() - lexeme.len() as u32 + 1,
                                 scanner.get_pos(),
-                                "non-periods must be preceded by an asterisk",
+                                "periods must be preceded by an asterisk",
                                 "directives are of form * {directive}",
-                            ));
+                            );
+                            // drop the list, or its names would be read as tokens
+                            read_periods(scanner, &mut Vec::new());
+                            return Err(err);
                         }
-
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::NonPeriods,
-                            byte_idx,
-                        });
-
-                        // read until next directive (*)
-                        read_periods(scanner, &mut tokens);
                     }
-                    "schedule" => {
-                        match &tokens[tokens.len() - 1].literal {
-                            Literal::Asterisk => {}
-                            _ => {
-                                return Err(CompilerError::new(
-                                    scanner.get_pos() - lexeme.len() as u32 + 1,
-                                    scanner.get_pos(),
-                                    "schedule must be preceded by an asterisk",
-                                    "directives are of form * {directive}",
-                                ));
-                            }
-                        }
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::Periods,
+                        byte_idx,
+                    });
 
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::Schedule,
-                            byte_idx,
-                        });
+                    // read until next directive (*)
+                    read_periods(scanner, tokens);
+                }
+                "non-periods" => {
+                    if !matches!(tokens.last().map(|t| &t.literal), Some(Literal::Asterisk)) {
+                        let err = CompilerError::new(
+                            scanner.get_pos() - lexeme.len() as u32 + 1,
+                            scanner.get_pos(),
+                            "non-periods must be preceded by an asterisk",
+                            "directives are of form * {directive}",
+                        );
+                        read_periods(scanner, &mut Vec::new());
+                        return Err(err);
                     }
-                    _ => {
-                        let regex = Regex::new(r#"^[a-z]+([a-z0-9]+[-])*[a-z0-9]+$"#).unwrap();
-                        if !regex.is_match(&lexeme) {
+
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::NonPeriods,
+                        byte_idx,
+                    });
+
+                    // read until next directive (*)
+                    read_periods(scanner, tokens);
+                }
+                "schedule" => {
+                    match tokens.last().map(|t| &t.literal) {
+                        Some(Literal::Asterisk) => {}
+                        _ => {
                             return Err(CompilerError::new(
-                                byte_idx,
-                                byte_idx + lexeme.len() as u32 - 1,
-                                "invalid identifier",
-                                "expected a-z, 0-9, -, must start with a-z, and end with a-z or 0-9"
+                                scanner.get_pos() - lexeme.len() as u32 + 1,
+                                scanner.get_pos(),
+                                "schedule must be preceded by an asterisk",
+                                "directives are of form * {directive}",
                             ));
                         }
+                    }
 
-                        tokens.push(Token {
-                            lexeme: lexeme.clone(),
-                            literal: Literal::Identifier(lexeme.clone()),
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::Schedule,
+                        byte_idx,
+                    });
+                }
+                _ => {
+                    if !identifier_regex.is_match(&lexeme) {
+                        return Err(CompilerError::new(
                             byte_idx,
-                        });
+                            byte_idx + lexeme.len() as u32 - 1,
+                            "invalid identifier",
+                            "expected a-z, 0-9, -, must start with a-z, and end with a-z or 0-9",
+                        ));
                     }
+
+                    tokens.push(Token {
+                        lexeme: lexeme.clone(),
+                        literal: Literal::Identifier(lexeme.clone()),
+                        byte_idx,
+                    });
                 }
             }
-            ' ' => {}
-            _ => {
-                return Err(CompilerError::new(
-                    scanner.get_pos(),
-                    scanner.get_pos(),
-                    &format!("unexpected character: {} (unicode: {})", c, c as u32),
-                    "expected a-z, A-Z, 0-9, *, #, \", -, :, /, \\n, and space",
-                ));
-            }
+        }
+        ' ' => {}
+        _ => {
+            return Err(CompilerError::new(
+                scanner.get_pos(),
+                scanner.get_pos(),
+                &format!("unexpected character: {} (unicode: {})", c, c as u32),
+                "expected a-z, A-Z, 0-9, *, #, \", -, :, /, \\n, and space",
+            ));
         }
     }
 
-    Ok(tokens)
+    Ok(())
 }
 
-pub fn make_tokens(scanner: &mut Scanner) -> Result<Vec<Token>> {
-    let res = tokenizer(scanner);
-
-    if let Err(e) = res {
-        let mut err_string = String::new();
-        e.fmt_for_terminal(&mut err_string, scanner)?;
-        return Err(anyhow!(err_string));
+pub fn make_tokens(scanner: &mut Scanner, diagnostics: &mut Diagnostics) -> Vec<Token> {
+    let (tokens, errors) = tokenizer(scanner);
+    for e in errors {
+        diagnostics.push(e.to_diagnostic(scanner));
     }
-
-    Ok(res?)
+    tokens
 }
@@ -1,6 +1,5 @@
-use anyhow::{anyhow, Result};
-use core::{fmt, panic};
-use std::{fs::File, io::Read};
+use crate::diagnostics::{Diagnostic, Severity};
+use core::fmt;
 
 pub struct Scanner {
     newline_pos: Vec<usize>,
@@ -10,21 +9,19 @@ pub struct Scanner {
 }
 
 impl Scanner {
-    pub fn new(filename: &str) -> Result<Self> {
-        let mut file = File::open(filename)
-            .map_err(|_| anyhow!(format!("Could not open file {}", filename)))?;
-        let mut buffer = String::new();
-        file.read_to_string(&mut buffer)?;
+    // scans `source`, naming it `filename` in errors
+    pub fn new(filename: &str, source: &str) -> Self {
+        let mut buffer: Vec<char> = source.chars().collect();
 
         // in case it is not already there
         buffer.push('\n');
 
-        Ok(Scanner {
+        Scanner {
             newline_pos: Vec::new(),
             pos: 0,
-            buffer: buffer.chars().collect(),
+            buffer,
             filename: filename.to_string(),
-        })
+        }
     }
 
     pub fn read_char(&mut self) -> Option<char> {
@@ -58,17 +55,16 @@ impl Scanner {
 
     pub fn get_line_col(&self, pos: usize) -> (usize, usize) {
         let mut line = 1;
-        let col;
 
         while line <= self.newline_pos.len() && pos > self.newline_pos[line - 1] {
             line += 1;
         }
 
-        if line > 1 {
-            col = pos - self.newline_pos[line - 2];
+        let col = if line > 1 {
+            pos - self.newline_pos[line - 2]
         } else {
-            col = pos + 1;
-        }
+            pos + 1
+        };
 
         (line, col)
     }
@@ -114,7 +110,7 @@ impl fmt::Display for CompilerError {
 
 impl CompilerError {
     pub fn new(from: u32, to: u32, msg: &str, hint: &str) -> Self {
-        let hint = if hint.len() != 0 {
+        let hint = if !hint.is_empty() {
             Some(hint.to_owned())
         } else {
             None
@@ -128,66 +124,34 @@ impl CompilerError {
         }
     }
 
-    pub fn get_err_str(&self, scanner: &Scanner) -> anyhow::Error {
-        let mut err_str = String::new();
-        self.fmt_for_terminal(&mut err_str, scanner).unwrap();
-        anyhow!(err_str)
-    }
-
-    pub fn fmt_for_terminal(&self, f: &mut dyn fmt::Write, scanner: &Scanner) -> Result<()> {
+    pub fn to_diagnostic(&self, scanner: &Scanner) -> Diagnostic {
         // assumes that self.from and self.to are on the same line
         // self.from and self.to can be u32::MAX if referring to the end of file
         let from = self.from.min(scanner.len());
         let to = self.to.min(scanner.len());
-        let first_level_indent: String = " ".repeat(4);
-        let excerpt_indent: String = " ".repeat(8);
 
         let (line, col) = scanner.get_line_col(from as usize);
 
-        let mut section = String::new();
-        {
-            let mut idx = scanner.get_start_of_line(line) as usize;
-            while let Some(c) = scanner.get_at(idx) {
-                if c == '\n' {
-                    break;
-                }
-                section.push(c);
-                idx += 1;
+        let mut excerpt = String::new();
+        let mut idx = scanner.get_start_of_line(line);
+        while let Some(c) = scanner.get_at(idx) {
+            if c == '\n' {
+                break;
             }
+            excerpt.push(c);
+            idx += 1;
         }
 
-        writeln!(f, "\x1b[1m\x1b[31mError\x1b[0m: \x1b[1m{}\x1b[0m", self.msg)?;
-        writeln!(
-            f,
-            "{}in {}:{}:{}\n",
-            first_level_indent,
-            scanner.get_filename(),
+        Diagnostic {
+            severity: Severity::Error,
+            file: scanner.get_filename().to_string(),
             line,
-            col
-        )?;
-
-        let line_padding = match line {
-            line if line < 10 => "  ",
-            line if line < 100 => " ",
-            line if line < 1000 => "",
-            _ => panic!("Too many lines in file"),
-        };
-        writeln!(
-            f,
-            "{}\x1b[1m\x1b[34m{}{}:\x1b[0m {}",
-            excerpt_indent, line_padding, line, section
-        )?;
-
-        writeln!(
-            f,
-            "{}{}\x1b[1m\x1b[31m{}\x1b[0m\n",
-            excerpt_indent,
-            " ".repeat(col + 4),
-            "^".repeat((to - from + 1) as usize)
-        )?;
-        if let Some(hint) = &self.hint {
-            writeln!(f, "{}\x1b[1mhint:\x1b[0m {}", first_level_indent, hint)?;
+            col,
+            from,
+            to,
+            msg: self.msg.clone(),
+            hint: self.hint.clone(),
+            excerpt,
         }
-        Ok(())
     }
 }
@@ -1,9 +1,9 @@
 use std::{collections::HashMap, vec};
 
-use anyhow::Result;
 use regex::Regex;
 
 use crate::{
+    diagnostics::{Diagnostic, Diagnostics, Severity},
     parser::{CalendarItem, Directive, DirectiveItem, Preset, Repeat},
     tokenizer::{Literal, Time, Token},
     utils::{CompilerError, Scanner},
@@ -18,51 +18,67 @@ pub struct SchoolInfo {
     pub calendar: Vec<CalendarItem>,
 }
 
-fn err_from_tok(tok: &Token, scanner: &Scanner, msg: &str, hint: &str) -> anyhow::Error {
+fn err_from_tok(tok: &Token, scanner: &Scanner, msg: &str, hint: &str) -> Diagnostic {
     CompilerError::new(
         tok.byte_idx,
         tok.byte_idx + tok.lexeme.len() as u32 - 1,
         msg,
         hint,
     )
-    .get_err_str(scanner)
+    .to_diagnostic(scanner)
 }
 
+// an error at the end of the file, for things missing from it
+fn err_at_end(scanner: &Scanner, msg: &str) -> Diagnostic {
+    CompilerError::new(scanner.len() - 1, scanner.len() - 1, msg, "").to_diagnostic(scanner)
+}
+
+// the periods and non-periods directives with their lists, and the presets
+type SchoolDirectives = (
+    (Token, Vec<Token>),
+    (Token, Vec<Token>),
+    HashMap<String, Preset>,
+);
+
+// a directive is missing if None is returned; the reason has been reported
 pub fn schedule_to_school_info(
-    mut ast: Vec<Directive>,
+    ast: Vec<Directive>,
     scanner: &Scanner,
-) -> Result<((Token, Repeat), Vec<CalendarItem>)> {
+    diagnostics: &mut Diagnostics,
+) -> Option<((Token, Repeat), Vec<CalendarItem>)> {
     let mut repeat = None;
     let mut calendar = None;
 
-    while let Some(directive) = ast.pop() {
+    for directive in ast {
         match directive.content {
             DirectiveItem::Repeat(r) => {
                 if repeat.is_some() {
-                    return Err(err_from_tok(
+                    diagnostics.push(err_from_tok(
                         &directive.begin_tok,
                         scanner,
                         "found more than one repeat directive",
                         "",
                     ));
+                    continue;
                 }
 
                 repeat = Some((directive.begin_tok, r));
             }
             DirectiveItem::Calendar(cal) => {
                 if calendar.is_some() {
-                    return Err(err_from_tok(
+                    diagnostics.push(err_from_tok(
                         &directive.begin_tok,
                         scanner,
                         "found more than one calendar directive",
                         "",
                     ));
+                    continue;
                 }
 
                 calendar = Some(cal);
             }
             _ => {
-                return Err(err_from_tok(
+                diagnostics.push(err_from_tok(
                     &directive.begin_tok,
                     scanner,
                     "found invalid directive in schedule",
@@ -73,71 +89,63 @@ pub fn schedule_to_school_info(
     }
 
     if repeat.is_none() {
-        let err = CompilerError::new(
-            scanner.len() - 1,
-            scanner.len() - 1,
-            "found no repeat directive in schedule",
-            "",
-        );
-        return Err(err.get_err_str(scanner));
+        diagnostics.push(err_at_end(scanner, "found no repeat directive in schedule"));
     }
 
     if calendar.is_none() {
-        let err = CompilerError::new(
-            scanner.len() - 1,
-            scanner.len() - 1,
+        diagnostics.push(err_at_end(
+            scanner,
             "found no calendar directive in schedule",
-            "",
-        );
-        return Err(err.get_err_str(scanner));
+        ));
     }
 
-    Ok((repeat.unwrap(), calendar.unwrap()))
+    Some((repeat?, calendar?))
 }
 
+// a directive is missing if None is returned; the reason has been reported
 pub fn school_to_school_info(
-    mut ast: Vec<Directive>,
+    ast: Vec<Directive>,
     scanner: &Scanner,
-) -> Result<(
-    (Token, Vec<Token>),
-    (Token, Vec<Token>),
-    HashMap<String, Preset>,
-)> {
+    diagnostics: &mut Diagnostics,
+) -> Option<SchoolDirectives> {
     let mut periods = None;
     let mut non_periods = None;
     let mut presets = HashMap::new();
 
-    while let Some(directive) = ast.pop() {
+    for directive in ast {
         match directive.content {
             DirectiveItem::Periods(periods_vec) => {
                 if periods.is_some() {
-                    return Err(err_from_tok(
+                    diagnostics.push(err_from_tok(
                         &directive.begin_tok,
                         scanner,
                         "found more than one periods directive",
                         "",
                     ));
+                    continue;
                 }
 
                 periods = Some((directive.begin_tok, periods_vec));
             }
             DirectiveItem::NonPeriods(nonperiods_vec) => {
                 if non_periods.is_some() {
-                    return Err(err_from_tok(
+                    diagnostics.push(err_from_tok(
                         &directive.begin_tok,
                         scanner,
                         "found more than one non-periods directive",
                         "",
                     ));
+                    continue;
                 }
 
                 non_periods = Some((directive.begin_tok, nonperiods_vec));
             }
             DirectiveItem::Preset(preset) => {
-                presets.insert(preset.ident.lexeme.clone(), preset);
+                // the first preset with a name is the one used
+                presets.entry(preset.ident.lexeme.clone()).or_insert(preset);
             }
             _ => {
-                return Err(err_from_tok(
+                diagnostics.push(err_from_tok(
                     &directive.begin_tok,
                     scanner,
                     "found invalid directive in school file",
@@ -148,36 +156,25 @@ pub fn school_to_school_info(
     }
 
     if periods.is_none() {
-        let err = CompilerError::new(
-            scanner.len() - 1,
-            scanner.len() - 1,
+        diagnostics.push(err_at_end(
+            scanner,
             "found no periods directive in school file",
-            "",
-        );
-        return Err(err.get_err_str(scanner));
+        ));
     }
 
     if non_periods.is_none() {
-        let err = CompilerError::new(
-            scanner.len() - 1,
-            scanner.len() - 1,
+        diagnostics.push(err_at_end(
+            scanner,
             "found no non-periods directive in school file",
-            "",
-        );
-        return Err(err.get_err_str(scanner));
+        ));
     }
 
-    if presets.len() == 0 {
-        let err = CompilerError::new(
-            scanner.len() - 1,
-            scanner.len() - 1,
-            "found no presets in school file",
-            "",
-        );
-        return Err(err.get_err_str(scanner));
+    if presets.is_empty() {
+        diagnostics.push(err_at_end(scanner, "found no presets in school file"));
+        return None;
     }
 
-    Ok((periods.unwrap(), non_periods.unwrap(), presets))
+    Some((periods?, non_periods?, presets))
 }
 
 const DEFAULT_NON_PERIODS: [&str; 5] = ["Free", "Brunch", "Break", "Lunch", "Passing"];
@@ -186,7 +183,8 @@ pub fn high_level_verifier(
     school_info: &SchoolInfo,
     school_scanner: &Scanner,
     schedule_scanner: &Scanner,
-) -> Result<()> {
+    diagnostics: &mut Diagnostics,
+) {
     // ensure no overlap between periods and non-periods
 
     let mut periods = vec![];
@@ -195,8 +193,8 @@ pub fn high_level_verifier(
 
     for period in school_info.periods.1.iter() {
         if DEFAULT_NON_PERIODS.contains(&period.lexeme.as_str()) {
-            return Err(err_from_tok(
-                &period,
+            diagnostics.push(err_from_tok(
+                period,
                 school_scanner,
                 &format!("found non-period `{}` in periods", period.lexeme),
                 &format!(
@@ -206,16 +204,14 @@ pub fn high_level_verifier(
             ));
         }
         if !event_regex.is_match(&period.lexeme) {
-            return Err(err_from_tok(
-                &period,
+            diagnostics.push(err_from_tok(
+                period,
                 school_scanner,
                 &format!(
                     "invalid period name `{}`; contains invalid characters",
                     period.lexeme
                 ),
-                &format!(
-                    "period names must only contain letters, numbers, spaces, colons, and slashes"
-                ),
+                "period names must only contain letters, numbers, spaces, colons, and slashes",
             ));
         }
         periods.push(period.lexeme.clone());
@@ -223,8 +219,8 @@ pub fn high_level_verifier(
 
     for non_period in school_info.non_periods.1.iter() {
         if periods.contains(&non_period.lexeme) {
-            return Err(err_from_tok(
-                &non_period,
+            diagnostics.push(err_from_tok(
+                non_period,
                 school_scanner,
                 &format!("found `{}` in non-periods", non_period.lexeme),
                 &format!(
@@ -235,8 +231,8 @@ pub fn high_level_verifier(
         }
 
         if DEFAULT_NON_PERIODS.contains(&non_period.lexeme.as_str()) {
-            return Err(err_from_tok(
-                &non_period,
+            diagnostics.push(err_from_tok(
+                non_period,
                 school_scanner,
                 &format!(
                     "found default non-period `{}` in non-periods",
@@ -250,13 +246,14 @@ pub fn high_level_verifier(
         }
 
         if !event_regex.is_match(&non_period.lexeme) {
-            return Err(err_from_tok(
-                &non_period,
+            diagnostics.push(err_from_tok(
+                non_period,
                 school_scanner,
-                &format!("invalid non-period name `{}`; contains invalid characters", non_period.lexeme),
                 &format!(
-                    "non-period names must only contain letters, numbers, spaces, colons, and slashes"
+                    "invalid non-period name `{}`; contains invalid characters",
+                    non_period.lexeme
                 ),
+                "non-period names must only contain letters, numbers, spaces, colons, and slashes",
             ));
         }
 
@@ -275,7 +272,7 @@ pub fn high_level_verifier(
         for i in 0..preset.event_list.len() {
             let event = &preset.event_list[i];
             if !all_events.contains(&event.name.lexeme) {
-                return Err(err_from_tok(
+                diagnostics.push(err_from_tok(
                     &event.name,
                     school_scanner,
                     &format!(
@@ -291,34 +288,28 @@ pub fn high_level_verifier(
 
             // ensure time orders are increasing and 24-hour time
             if let Literal::Time(time) = &event.from.literal {
-                if last_time.is_some() {
-                    if time.hour < last_time.unwrap().hour
-                        || (time.hour == last_time.unwrap().hour
-                            && time.minute < last_time.unwrap().minute)
-                    {
-                        return Err(err_from_tok(
-                            &event.from,
-                            school_scanner,
-                            "time order in a preset must be increasing",
-                            "are you using 24-hour time in hh:mm format?",
-                        ));
-                    }
+                if last_time.is_some_and(|last| (time.hour, time.minute) < (last.hour, last.minute))
+                {
+                    diagnostics.push(err_from_tok(
+                        &event.from,
+                        school_scanner,
+                        "time order in a preset must be increasing",
+                        "are you using 24-hour time in hh:mm format?",
+                    ));
                 }
-                last_time = Some(time.clone());
+                last_time = Some(*time);
             } else {
                 unreachable!();
             }
 
             // ensure each presets ends with a "Free"
-            if i == preset.event_list.len() - 1 {
-                if event.name.lexeme != "Free" {
-                    return Err(err_from_tok(
-                        &event.name,
-                        school_scanner,
-                        &format!("last event in preset `{}` must be `Free`", ident),
-                        "all presets must end with `Free`",
-                    ));
-                }
+            if i == preset.event_list.len() - 1 && event.name.lexeme != "Free" {
+                diagnostics.push(err_from_tok(
+                    &event.name,
+                    school_scanner,
+                    &format!("last event in preset `{}` must be `Free`", ident),
+                    "all presets must end with `Free`",
+                ));
             }
         }
     }
@@ -328,7 +319,7 @@ pub fn high_level_verifier(
     // validate calendar array
     for item in school_info.calendar.iter() {
         if !preset_names.contains(&item.preset.lexeme) {
-            return Err(err_from_tok(
+            diagnostics.push(err_from_tok(
                 &item.preset,
                 schedule_scanner,
                 &format!(
@@ -346,8 +337,8 @@ pub fn high_level_verifier(
     // assert repeats are in the presets
     for repeat in school_info.repeat.1.pattern.iter() {
         if !preset_names.contains(&repeat.lexeme) {
-            return Err(err_from_tok(
-                &repeat,
+            diagnostics.push(err_from_tok(
+                repeat,
                 schedule_scanner,
                 &format!("found invalid preset name `{}` in repeat", repeat.lexeme),
                 &format!(
@@ -357,11 +348,14 @@ pub fn high_level_verifier(
             ));
         }
     }
-
-    return Ok(());
 }
 
-pub fn prune(school_info: &mut SchoolInfo) {
+// removes the presets nothing refers to, warning about each
+pub fn prune(
+    school_info: &mut SchoolInfo,
+    school_scanner: &Scanner,
+    diagnostics: &mut Diagnostics,
+) {
     // TODO(arjun): prune old calendar items
 
     // want to remove presets that aren't used
@@ -382,6 +376,15 @@ pub fn prune(school_info: &mut SchoolInfo) {
         .collect();
 
     for preset in presets_to_delete {
-        school_info.presets.remove(&preset);
+        let preset = school_info.presets.remove(&preset).unwrap();
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            ..err_from_tok(
+                &preset.ident,
+                school_scanner,
+                &format!("preset `{}` is never used", preset.ident.lexeme),
+                "presets are used by the repeat pattern or the calendar in the schedule",
+            )
+        });
     }
 }
@@ -0,0 +1,128 @@
+use std::fs;
+
+use serde_json::Value;
+use timing_data::{compile, Severity};
+
+const SCHOOL: &str = "\
+* periods
+Period 1
+Period 2
+
+* non-periods
+Assembly
+
+* schedule regular \"Regular Schedule\"
+8:00 Period 1
+9:00 Passing
+9:10 Period 2
+10:00 Free
+
+* schedule assembly \"Assembly Schedule\"
+8:00 Assembly
+9:00 Period 1
+10:00 Free
+";
+
+const SCHEDULE: &str = "\
+* repeat 2024/08/12
+regular
+
+* calendar
+2024/09/02 assembly
+2024/11/25-2024/11/29 regular \"Thanksgiving\"
+";
+
+#[test]
+fn compiles_strings() {
+    let compiled = compile(SCHOOL, SCHEDULE).unwrap();
+
+    let school: Value = serde_json::from_str(&compiled.school).unwrap();
+    assert_eq!(school["periods"][1], "Period 2");
+    assert_eq!(school["nonPeriods"][0], "Assembly");
+    assert_eq!(school["presets"]["regular"]["s"][2]["f"], "9:10");
+
+    let schedule: Value = serde_json::from_str(&compiled.schedule).unwrap();
+    assert_eq!(schedule["defaults"]["start"], "8/12/2024");
+    assert_eq!(schedule["calendar"][0]["date"], "9/2/2024");
+    assert_eq!(schedule["calendar"][1]["content"]["n"], "Thanksgiving");
+}
+
+#[test]
+fn reports_where_the_error_is() {
+    let school = SCHOOL.replace("9:00 Passing", "9:00 Recess");
+    let diagnostics = compile(&school, SCHEDULE).unwrap_err();
+
+    let diagnostic = diagnostics.iter().next().unwrap();
+    assert_eq!(diagnostic.file, "school.txt");
+    assert_eq!((diagnostic.line, diagnostic.col), (10, 6));
+    assert_eq!(diagnostic.excerpt, "9:00 Recess");
+    assert!(diagnostic.msg.contains("`Recess`"));
+}
+
+#[test]
+fn reports_every_error_in_order() {
+    let school = SCHOOL
+        .replace("9:00 Passing", "9:00 Recess")
+        .replace("8:00 Assembly", "8:00 Asembly");
+    let schedule = SCHEDULE.replace("2024/09/02 assembly", "2024/09/02 asembly");
+    let diagnostics = compile(&school, &schedule).unwrap_err();
+
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.file.as_str(), d.line))
+        .collect();
+    assert_eq!(
+        found,
+        [("school.txt", 10), ("school.txt", 15), ("schedule.txt", 5)]
+    );
+}
+
+#[test]
+fn recovers_after_a_bad_line() {
+    // a tokenizer error, then parser errors in both files
+    let school = SCHOOL
+        .replace("9:10 Period 2", "9:1x Period 2")
+        .replace("* schedule assembly", "* schedule");
+    let schedule = SCHEDULE.replace("2024/09/02 assembly", "2024/09/02");
+    let diagnostics = compile(&school, &schedule).unwrap_err();
+
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.file.as_str(), d.line))
+        .collect();
+    assert_eq!(
+        found,
+        [("school.txt", 11), ("school.txt", 14), ("schedule.txt", 5)]
+    );
+    assert!(diagnostics.warnings().next().is_none());
+}
+
+#[test]
+fn warns_about_unused_presets() {
+    let schedule = SCHEDULE.replace("2024/09/02 assembly", "2024/09/02 regular");
+    let compiled = compile(SCHOOL, &schedule).unwrap();
+
+    let warning = compiled.warnings.iter().next().unwrap();
+    assert_eq!(warning.severity, Severity::Warning);
+    assert_eq!((warning.line, warning.col), (14, 12));
+    assert!(warning.msg.contains("`assembly`"));
+
+    let school: Value = serde_json::from_str(&compiled.school).unwrap();
+    assert!(school["presets"].get("assembly").is_none());
+}
+
+#[test]
+fn compiles_every_bundled_school() {
+    for entry in fs::read_dir("data").unwrap() {
+        let path = entry.unwrap().path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let school = fs::read_to_string(path.join("school.txt")).unwrap();
+        let schedule = fs::read_to_string(path.join("schedule.txt")).unwrap();
+        if let Err(diagnostics) = compile(&school, &schedule) {
+            panic!("{}: {}", path.display(), diagnostics);
+        }
+    }
+}